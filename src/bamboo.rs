@@ -1,4 +1,10 @@
-use crate::build_status::{BuildStatus, Status, TimeInfo};
+use futures::{FutureExt, future::BoxFuture};
+
+use crate::{
+    build_status::{BuildStatus, RunningInfo, Status, TimeInfo},
+    error::Error,
+    provider::{Capabilities, Provider},
+};
 
 #[derive(Debug, serde::Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
@@ -19,33 +25,85 @@ struct BambooResult {
     build_state: String,
     life_cycle_state: String,
     build_result_key: String,
-    build_completed_time: String,
-    build_duration: u32,
+    build_started_time: Option<String>,
+    build_completed_time: Option<String>,
+    build_duration: Option<u32>,
+    progress: Option<BambooProgress>,
+}
+
+#[derive(Debug, serde::Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct BambooProgress {
+    average_build_duration: Option<u32>,
+}
+
+impl BambooResult {
+    fn url(&self, server_url: &str) -> String {
+        format!("{server_url}/browse/{}", self.build_result_key)
+    }
+
+    fn to_running_info(
+        &self,
+        server_url: &str,
+        now: chrono::DateTime<chrono::FixedOffset>,
+    ) -> Option<RunningInfo> {
+        let started_at = self.build_started_time.as_ref()?;
+        let started_at = chrono::DateTime::parse_from_rfc3339(started_at).ok()?;
+        let estimated_secs = self
+            .progress
+            .as_ref()
+            .and_then(|progress| progress.average_build_duration)
+            .map(|millis| millis / 1000);
+        Some(RunningInfo {
+            url: Some(self.url(server_url)),
+            ..RunningInfo::new(started_at, now, estimated_secs)
+        })
+    }
+}
+
+fn to_status(life_cycle_state: &str, build_state: &str) -> Status {
+    match (life_cycle_state, build_state) {
+        ("Finished", "Successful") => Status::Green,
+        ("Finished", "Failed") => Status::Red,
+        ("InProgress", _) => Status::Running,
+        ("Queued" | "Pending", _) => Status::Pending,
+        ("NotBuilt", _) => Status::Cancelled,
+        _ => Status::Unknown,
+    }
 }
 
 impl BambooResponse {
-    fn to_build_status(&self, server_url: &String) -> Option<BuildStatus> {
-        self.results
-            .result
-            .get(0)
-            .filter(|result| result.life_cycle_state == "Finished")
+    fn to_build_status(
+        &self,
+        server_url: &str,
+        now: chrono::DateTime<chrono::FixedOffset>,
+    ) -> Option<BuildStatus> {
+        let results = &self.results.result;
+        let running = results
+            .iter()
+            .find(|result| result.life_cycle_state == "InProgress")
+            .and_then(|result| result.to_running_info(server_url, now));
+        // results are sorted by most recent first, fall back to the
+        // in progress / queued build if nothing has finished yet
+        results
+            .iter()
+            .find(|result| result.life_cycle_state == "Finished")
+            .or(results.first())
             .map(|result| {
-                let status = if result.build_state == "Successful" {
-                    Status::Green
-                } else {
-                    Status::Red
-                };
-                let server_url = server_url;
-                let build_result_key = &result.build_result_key;
-                let url = format!("{server_url}/browse/{build_result_key}");
-                let time_info = TimeInfo {
-                    completed_at: result.build_completed_time.clone(),
-                    duration_secs: result.build_duration / 1000,
+                let status = to_status(&result.life_cycle_state, &result.build_state);
+                let url = result.url(server_url);
+                let time_info = match (&result.build_completed_time, result.build_duration) {
+                    (Some(completed_at), Some(duration)) => Some(TimeInfo {
+                        completed_at: completed_at.clone(),
+                        duration_secs: Some(duration / 1000),
+                    }),
+                    _ => None,
                 };
                 BuildStatus {
                     status,
                     url,
-                    time_info: Some(time_info),
+                    time_info,
+                    running,
                 }
             })
     }
@@ -55,9 +113,9 @@ pub async fn fetch(
     server_url: &String,
     plan: &String,
     token: &Option<String>,
-) -> Result<BuildStatus, String> {
+) -> Result<BuildStatus, Error> {
     let url = format!(
-        "{server_url}/rest/api/latest/result/{plan}.json?max-results=1&expand=results.result"
+        "{server_url}/rest/api/latest/result/{plan}.json?max-results=5&includeAllStates=true&expand=results.result"
     );
 
     let headers = match token {
@@ -69,10 +127,46 @@ pub async fn fetch(
 
     crate::utils::request::<BambooResponse>(&url, &headers)
         .await
-        .and_then(|r| match r.to_build_status(server_url) {
-            Some(build_status) => Ok(build_status),
-            None => Err(String::from("No build found in response")),
-        })
+        .and_then(
+            |r| match r.to_build_status(server_url, chrono::Utc::now().fixed_offset()) {
+                Some(build_status) => Ok(build_status),
+                None => Err(Error::NoBuild),
+            },
+        )
+}
+
+/// A Bamboo plan, its latest result whatever the branch
+#[derive(Debug, serde::Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BambooConfig {
+    pub server_url: String,
+    pub plan: String,
+    pub token: Option<String>,
+}
+
+impl Provider for BambooConfig {
+    fn tag(&self) -> &'static str {
+        "bamboo"
+    }
+
+    fn title(&self) -> String {
+        self.plan.to_string()
+    }
+
+    fn web_url(&self) -> String {
+        format!("{}/browse/{}", self.server_url, self.plan)
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            time_info: true,
+            running: true,
+        }
+    }
+
+    fn fetch(&self) -> BoxFuture<'_, Result<BuildStatus, Error>> {
+        fetch(&self.server_url, &self.plan, &self.token).boxed()
+    }
 }
 
 #[cfg(test)]
@@ -92,8 +186,10 @@ mod bamboo_tests {
                     build_state: String::from("Failed"),
                     life_cycle_state: String::from("Finished"),
                     build_result_key: String::from("TRUNK-DTRTMP-2203"),
-                    build_completed_time: String::from("2025-11-07T09:19:46.000+01:00"),
-                    build_duration: 1266000,
+                    build_started_time: Some(String::from("2025-11-07T08:58:40.000+01:00")),
+                    build_completed_time: Some(String::from("2025-11-07T09:19:46.000+01:00")),
+                    build_duration: Some(1266000),
+                    progress: None,
                 }],
             },
         };
@@ -109,8 +205,10 @@ mod bamboo_tests {
                     build_state: String::from("Failed"),
                     life_cycle_state: String::from("Finished"),
                     build_result_key: String::from("TRUNK-DTRTMP-2203"),
-                    build_completed_time: String::from("2025-11-07T09:19:46.000+01:00"),
-                    build_duration: 1266000,
+                    build_started_time: Some(String::from("2025-11-07T08:58:40.000+01:00")),
+                    build_completed_time: Some(String::from("2025-11-07T09:19:46.000+01:00")),
+                    build_duration: Some(1266000),
+                    progress: None,
                 }],
             },
         };
@@ -119,10 +217,70 @@ mod bamboo_tests {
             url: String::from("http://my.bamboo/browse/TRUNK-DTRTMP-2203"),
             time_info: Some(TimeInfo {
                 completed_at: String::from("2025-11-07T09:19:46.000+01:00"),
-                duration_secs: 1266,
+                duration_secs: Some(1266),
             }),
+            running: None,
         };
         let url = String::from("http://my.bamboo");
-        assert_eq!(response.to_build_status(&url).unwrap(), expected);
+        let now = chrono::Utc::now().fixed_offset();
+        assert_eq!(response.to_build_status(&url, now).unwrap(), expected);
+    }
+
+    #[test]
+    fn convert_running() {
+        let response = BambooResponse {
+            results: BambooResults {
+                size: 2,
+                result: vec![
+                    BambooResult {
+                        build_state: String::from("Unknown"),
+                        life_cycle_state: String::from("InProgress"),
+                        build_result_key: String::from("TRUNK-DTRTMP-2204"),
+                        build_started_time: Some(String::from("2025-11-07T10:00:00.000+01:00")),
+                        build_completed_time: None,
+                        build_duration: None,
+                        progress: Some(BambooProgress {
+                            average_build_duration: Some(1266000),
+                        }),
+                    },
+                    BambooResult {
+                        build_state: String::from("Successful"),
+                        life_cycle_state: String::from("Finished"),
+                        build_result_key: String::from("TRUNK-DTRTMP-2203"),
+                        build_started_time: Some(String::from("2025-11-07T08:58:40.000+01:00")),
+                        build_completed_time: Some(String::from("2025-11-07T09:19:46.000+01:00")),
+                        build_duration: Some(1266000),
+                        progress: None,
+                    },
+                ],
+            },
+        };
+        let url = String::from("http://my.bamboo");
+        let now = chrono::DateTime::parse_from_rfc3339("2025-11-07T10:03:00+01:00").unwrap();
+        let build_status = response.to_build_status(&url, now).unwrap();
+        assert_eq!(build_status.status, Status::Green);
+        assert_eq!(
+            build_status.url,
+            "http://my.bamboo/browse/TRUNK-DTRTMP-2203"
+        );
+        assert_eq!(
+            build_status.running,
+            Some(RunningInfo {
+                started_at: Some(String::from("2025-11-07T10:00:00+01:00")),
+                elapsed_secs: Some(180),
+                estimated_secs: Some(1266),
+                url: Some(String::from("http://my.bamboo/browse/TRUNK-DTRTMP-2204")),
+            })
+        );
+    }
+
+    #[test]
+    fn map_states() {
+        assert_eq!(to_status("Finished", "Successful"), Status::Green);
+        assert_eq!(to_status("Finished", "Failed"), Status::Red);
+        assert_eq!(to_status("Finished", "Unknown"), Status::Unknown);
+        assert_eq!(to_status("InProgress", "Unknown"), Status::Running);
+        assert_eq!(to_status("Queued", "Unknown"), Status::Pending);
+        assert_eq!(to_status("NotBuilt", "Unknown"), Status::Cancelled);
     }
 }
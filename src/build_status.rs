@@ -1,18 +1,117 @@
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct TimeInfo {
     pub completed_at: String,
-    pub duration_secs: u32
+    /// Unknown for some providers, e.g. CCTray feeds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_secs: Option<u32>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Status {
     Green,
-    Red
+    Red,
+    /// The build is in progress
+    Running,
+    /// The build is queued, or waiting for approval
+    Pending,
+    /// The build was cancelled, aborted or skipped
+    Cancelled,
+    /// The build completed, but has test failures or warnings
+    Unstable,
+    /// The CI reported a state we cannot interpret
+    Unknown,
 }
 
-#[derive(Debug, PartialEq)]
+/// A build that is currently in progress
+#[derive(Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunningInfo {
+    /// Unknown for some providers, e.g. CCTray feeds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub elapsed_secs: Option<u32>,
+    pub estimated_secs: Option<u32>,
+    /// Link to the build in progress
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+impl RunningInfo {
+    pub fn new(
+        started_at: chrono::DateTime<chrono::FixedOffset>,
+        now: chrono::DateTime<chrono::FixedOffset>,
+        estimated_secs: Option<u32>,
+    ) -> RunningInfo {
+        let elapsed_secs = (now - started_at).num_seconds().max(0);
+        RunningInfo {
+            started_at: Some(started_at.to_rfc3339()),
+            elapsed_secs: Some(u32::try_from(elapsed_secs).unwrap_or(u32::MAX)),
+            estimated_secs,
+            url: None,
+        }
+    }
+}
+
+/// The last completed build, along with the build in progress if any
+#[derive(Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct BuildStatus {
-    pub status: Status, 
-    pub url: String, 
-    pub time_info: Option<TimeInfo>
-}
\ No newline at end of file
+    pub status: Status,
+    pub url: String,
+    pub time_info: Option<TimeInfo>,
+    pub running: Option<RunningInfo>,
+}
+
+/// Status of a whole from the ones of its parts, e.g. the workflows of a
+/// pipeline : red if any failed, running if any is still running, green only
+/// if all succeeded
+pub fn aggregate(statuses: &[Status]) -> Status {
+    if statuses.is_empty() {
+        return Status::Unknown;
+    }
+    [
+        Status::Red,
+        Status::Unstable,
+        Status::Running,
+        Status::Pending,
+        Status::Cancelled,
+        Status::Unknown,
+    ]
+    .into_iter()
+    .find(|status| statuses.contains(status))
+    .unwrap_or(Status::Green)
+}
+
+#[cfg(test)]
+mod build_status_tests {
+    use super::*;
+
+    #[test]
+    fn aggregate_statuses() {
+        assert_eq!(aggregate(&[Status::Green, Status::Green]), Status::Green);
+        assert_eq!(
+            aggregate(&[Status::Green, Status::Running, Status::Red]),
+            Status::Red
+        );
+        assert_eq!(
+            aggregate(&[Status::Running, Status::Green]),
+            Status::Running
+        );
+        assert_eq!(
+            aggregate(&[Status::Pending, Status::Green]),
+            Status::Pending
+        );
+        assert_eq!(
+            aggregate(&[Status::Green, Status::Cancelled]),
+            Status::Cancelled
+        );
+        assert_eq!(
+            aggregate(&[Status::Unstable, Status::Running]),
+            Status::Unstable
+        );
+        assert_eq!(aggregate(&[]), Status::Unknown);
+    }
+}
@@ -0,0 +1,329 @@
+use futures::{FutureExt, future::BoxFuture};
+use regex::Regex;
+
+use crate::{
+    build_status::{BuildStatus, RunningInfo, Status, TimeInfo, aggregate},
+    config::BuildConfig,
+    error::Error,
+    provider::{Capabilities, Provider},
+};
+
+/// A `<Project>` of a CCTray feed, as exposed by GoCD, TeamCity, Jenkins'
+/// `cc.xml`, Concourse...
+#[derive(Debug, serde::Deserialize, serde::Serialize, PartialEq)]
+struct Project {
+    #[serde(rename = "@name")]
+    name: String,
+    /// `Sleeping`, `Building` or `CheckingModifications`
+    #[serde(rename = "@activity")]
+    activity: String,
+    /// `Success`, `Failure`, `Exception` or `Unknown`
+    #[serde(rename = "@lastBuildStatus")]
+    last_build_status: String,
+    #[serde(
+        rename = "@lastBuildTime",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    last_build_time: Option<String>,
+    #[serde(rename = "@webUrl")]
+    web_url: String,
+}
+
+#[derive(Debug, serde::Deserialize, serde::Serialize, PartialEq)]
+#[serde(rename = "Projects")]
+struct Projects {
+    #[serde(rename = "Project", default)]
+    projects: Vec<Project>,
+}
+
+/// Projects of a CCTray feed, aggregated when several match
+#[derive(Debug, serde::Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CCTrayConfig {
+    /// The feed, e.g. `https://gocd.example.com/go/cctray.xml`
+    pub url: String,
+    /// Name of the project in the feed, or a regex when `regex` is set
+    pub project: String,
+    #[serde(default)]
+    pub regex: bool,
+    /// Names the build, the `project` by default
+    pub title: Option<String>,
+    /// Basic auth along with the token
+    pub user: Option<String>,
+    pub token: Option<String>,
+}
+
+/// The last result, a project being built again keeps it
+fn to_status(project: &Project) -> Status {
+    match project.last_build_status.as_str() {
+        "Success" => Status::Green,
+        "Failure" | "Exception" => Status::Red,
+        _ => Status::Unknown,
+    }
+}
+
+/// Feeds do not always tell the offset, UTC is assumed then
+fn parse_time(s: &str) -> Option<chrono::DateTime<chrono::FixedOffset>> {
+    chrono::DateTime::parse_from_rfc3339(s).ok().or_else(|| {
+        chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.f")
+            .ok()
+            .map(|date| date.and_utc().fixed_offset())
+    })
+}
+
+/// The feed has no durations, only the last build time is known
+fn to_time_info(projects: &[&Project]) -> Option<TimeInfo> {
+    projects
+        .iter()
+        .filter_map(|p| p.last_build_time.as_deref().and_then(parse_time))
+        .max()
+        .map(|completed_at| TimeInfo {
+            completed_at: completed_at.to_rfc3339(),
+            duration_secs: None,
+        })
+}
+
+impl CCTrayConfig {
+    pub fn matcher(&self) -> Result<Option<Regex>, Error> {
+        if !self.regex {
+            return Ok(None);
+        }
+        Regex::new(&self.project)
+            .map(Some)
+            .map_err(|e| Error::Config(format!("invalid project regex {} : {e}", self.project)))
+    }
+
+    fn to_build_status(&self, feed: &Projects) -> Result<BuildStatus, Error> {
+        let matcher = self.matcher()?;
+        let projects: Vec<&Project> = feed
+            .projects
+            .iter()
+            .filter(|p| match &matcher {
+                Some(regex) => regex.is_match(&p.name),
+                None => p.name == self.project,
+            })
+            .collect();
+        let statuses: Vec<Status> = projects.iter().map(|p| to_status(p)).collect();
+        // the feed does not tell when builds started
+        let running = projects
+            .iter()
+            .any(|p| p.activity == "Building")
+            .then_some(RunningInfo {
+                started_at: None,
+                elapsed_secs: None,
+                estimated_secs: None,
+                url: None,
+            });
+        let status = aggregate(&statuses);
+        // link to the project that gives its status to the whole
+        let project = projects
+            .iter()
+            .zip(statuses.iter())
+            .find(|(_, s)| **s == status)
+            .map(|(p, _)| p)
+            .ok_or(Error::NoBuild)?;
+        Ok(BuildStatus {
+            status,
+            url: project.web_url.to_string(),
+            time_info: to_time_info(&projects),
+            running,
+        })
+    }
+}
+
+pub async fn fetch(config: &CCTrayConfig) -> Result<BuildStatus, Error> {
+    let headers = vec![(String::from("Accept"), String::from("application/xml"))];
+    let feed =
+        crate::utils::request_xml::<Projects>(&config.url, &headers, &config.user, &config.token)
+            .await?;
+    config.to_build_status(&feed)
+}
+
+impl Provider for CCTrayConfig {
+    fn tag(&self) -> &'static str {
+        "cctray"
+    }
+
+    fn title(&self) -> String {
+        self.title.as_ref().unwrap_or(&self.project).to_string()
+    }
+
+    fn web_url(&self) -> String {
+        self.url.to_string()
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            time_info: true,
+            running: true,
+        }
+    }
+
+    fn validate(&self) -> Vec<String> {
+        match self.matcher() {
+            Err(Error::Config(message)) => vec![message],
+            _ => Vec::new(),
+        }
+    }
+
+    fn fetch(&self) -> BoxFuture<'_, Result<BuildStatus, Error>> {
+        fetch(self).boxed()
+    }
+}
+
+/// CCTray has no unstable or cancelled builds, builds that cannot be fetched
+/// are reported as `Exception`
+fn to_project(build: &BuildConfig, result: &Result<BuildStatus, Error>) -> Project {
+    let (activity, last_build_status) = match result {
+        Ok(build_status) => {
+            let building = build_status.status == Status::Running || build_status.running.is_some();
+            let last_build_status = match build_status.status {
+                Status::Green => "Success",
+                Status::Red | Status::Unstable => "Failure",
+                // `Running` when no build has completed yet
+                Status::Running | Status::Pending | Status::Cancelled | Status::Unknown => {
+                    "Unknown"
+                }
+            };
+            let activity = if building { "Building" } else { "Sleeping" };
+            (activity, last_build_status)
+        }
+        Err(_) => ("Sleeping", "Exception"),
+    };
+    let (last_build_time, web_url) = match result {
+        Ok(build_status) => (
+            build_status
+                .time_info
+                .as_ref()
+                .map(|t| t.completed_at.to_string()),
+            build_status.url.to_string(),
+        ),
+        Err(_) => (None, build.provider().web_url()),
+    };
+    Project {
+        name: build.get_title(),
+        activity: activity.to_string(),
+        last_build_status: last_build_status.to_string(),
+        last_build_time,
+        web_url,
+    }
+}
+
+/// The results as a CCTray feed, for tray apps and radiators
+pub fn to_feed(builds: &[BuildConfig], results: &[Result<BuildStatus, Error>]) -> String {
+    let feed = Projects {
+        projects: builds
+            .iter()
+            .zip(results.iter())
+            .map(|(build, result)| to_project(build, result))
+            .collect(),
+    };
+    let xml = quick_xml::se::to_string(&feed).unwrap();
+    format!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n{xml}")
+}
+
+#[cfg(test)]
+mod cctray_tests {
+    use super::*;
+
+    const FEED: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<Projects>
+  <Project name="my-pipeline :: build" activity="Sleeping" lastBuildStatus="Success" lastBuildLabel="12" lastBuildTime="2025-11-07T09:19:46+01:00" webUrl="https://gocd.example.com/go/pipelines/my-pipeline/12/build/1"/>
+  <Project name="my-pipeline :: deploy" activity="Sleeping" lastBuildStatus="Failure" lastBuildLabel="11" lastBuildTime="2025-11-07T08:00:00.1234567" webUrl="https://gocd.example.com/go/pipelines/my-pipeline/11/deploy/1"/>
+  <Project name="other" activity="Building" lastBuildStatus="Failure" webUrl="https://gocd.example.com/go/pipelines/other"/>
+</Projects>"#;
+
+    fn config(project: &str, regex: bool) -> CCTrayConfig {
+        CCTrayConfig {
+            url: String::from("https://gocd.example.com/go/cctray.xml"),
+            project: String::from(project),
+            regex,
+            title: None,
+            user: None,
+            token: None,
+        }
+    }
+
+    #[test]
+    fn select_projects() {
+        let feed = crate::utils::parse_xml::<Projects>(FEED).unwrap();
+        assert_eq!(feed.projects.len(), 3);
+        let expected = BuildStatus {
+            status: Status::Green,
+            url: String::from("https://gocd.example.com/go/pipelines/my-pipeline/12/build/1"),
+            time_info: Some(TimeInfo {
+                completed_at: String::from("2025-11-07T09:19:46+01:00"),
+                duration_secs: None,
+            }),
+            running: None,
+        };
+        let status = config("my-pipeline :: build", false).to_build_status(&feed);
+        assert_eq!(status, Ok(expected));
+        // no duration is made up
+        assert_eq!(
+            serde_json::to_value(status.unwrap().time_info).unwrap(),
+            serde_json::json!({ "completedAt": "2025-11-07T09:19:46+01:00" })
+        );
+        let status = config("^my-pipeline", true).to_build_status(&feed).unwrap();
+        assert_eq!(status.status, Status::Red);
+        assert_eq!(
+            status.url,
+            "https://gocd.example.com/go/pipelines/my-pipeline/11/deploy/1"
+        );
+        // a red project being built again stays red
+        let status = config("other", false).to_build_status(&feed).unwrap();
+        assert_eq!(status.status, Status::Red);
+        assert_eq!(status.time_info, None);
+        assert_eq!(
+            status.running,
+            Some(RunningInfo {
+                started_at: None,
+                elapsed_secs: None,
+                estimated_secs: None,
+                url: None,
+            })
+        );
+        let status = config("missing", false).to_build_status(&feed);
+        assert_eq!(status, Err(Error::NoBuild));
+    }
+
+    #[test]
+    fn feed() {
+        let builds: Vec<BuildConfig> = serde_json::from_str(
+            "[{\"tag\":\"bamboo\",\"serverUrl\":\"http://my.bamboo\",\"plan\":\"MY-PLAN\"},{\"tag\":\"cctray\",\"url\":\"https://gocd.example.com/go/cctray.xml\",\"project\":\"other\"},{\"tag\":\"jenkins\",\"serverUrl\":\"http://my.jenkins\",\"plan\":\"my-job\",\"branch\":\"main\"}]",
+        )
+        .unwrap();
+        let results = vec![
+            Ok(BuildStatus {
+                status: Status::Unstable,
+                url: String::from("http://my.bamboo/browse/MY-PLAN-12"),
+                time_info: Some(TimeInfo {
+                    completed_at: String::from("2025-11-07T09:19:46.000+01:00"),
+                    duration_secs: Some(1266),
+                }),
+                running: None,
+            }),
+            Err(Error::Timeout),
+            Ok(BuildStatus {
+                status: Status::Green,
+                url: String::from("http://my.jenkins/job/my-job/41/"),
+                time_info: None,
+                running: Some(RunningInfo {
+                    started_at: Some(String::from("2025-11-07T09:30:00+01:00")),
+                    elapsed_secs: Some(60),
+                    estimated_secs: None,
+                    url: None,
+                }),
+            }),
+        ];
+        let feed = to_feed(&builds, &results);
+        assert_eq!(
+            feed,
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<Projects><Project name=\"MY-PLAN\" activity=\"Sleeping\" lastBuildStatus=\"Failure\" lastBuildTime=\"2025-11-07T09:19:46.000+01:00\" webUrl=\"http://my.bamboo/browse/MY-PLAN-12\"/><Project name=\"other\" activity=\"Sleeping\" lastBuildStatus=\"Exception\" webUrl=\"https://gocd.example.com/go/cctray.xml\"/><Project name=\"my-job/main\" activity=\"Building\" lastBuildStatus=\"Success\" webUrl=\"http://my.jenkins/job/my-job/41/\"/></Projects>"
+        );
+        // tray apps read it back
+        let projects = crate::utils::parse_xml::<Projects>(&feed).unwrap();
+        assert_eq!(projects.projects[0].name, "MY-PLAN");
+    }
+}
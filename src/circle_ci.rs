@@ -1,15 +1,62 @@
-use crate::build_status::BuildStatus;
+use futures::{FutureExt, future::BoxFuture};
+
+use crate::{
+    build_status::{BuildStatus, RunningInfo, Status, TimeInfo, aggregate},
+    error::Error,
+    provider::{Capabilities, Provider},
+    utils::encode_uri_component,
+};
 
 static BASE_URL: &str = "https://circleci.com/api/v2";
+static APP_URL: &str = "https://app.circleci.com";
+
+/// Where the project lives, the first part of its slug
+#[derive(Debug, Default, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Vcs {
+    #[default]
+    GitHub,
+    Bitbucket,
+    /// GitLab and standalone projects, identified by the ids of the
+    /// organization and project rather than their names
+    #[serde(alias = "gitlab")]
+    CircleCI,
+}
+
+impl Vcs {
+    fn slug(&self) -> &'static str {
+        match self {
+            Vcs::GitHub => "github",
+            Vcs::Bitbucket => "bitbucket",
+            Vcs::CircleCI => "circleci",
+        }
+    }
+}
+
+/// API and web app base URLs, of circleci.com or of a CircleCI Server
+fn base_urls(server_url: &Option<String>) -> (String, String) {
+    match server_url {
+        Some(server_url) => {
+            let server_url = server_url.trim_end_matches('/');
+            (format!("{server_url}/api/v2"), server_url.to_string())
+        }
+        None => (BASE_URL.to_string(), APP_URL.to_string()),
+    }
+}
+
+/// A named workflow does not run on every pipeline, look for it in the
+/// most recent ones
+const MAX_PIPELINES: usize = 5;
 
 #[derive(Debug, serde::Deserialize, PartialEq)]
-struct CircleCIResponse {
-    items: Vec<CircleCIItem>,
+struct PipelineResponse {
+    items: Vec<PipelineItem>,
 }
 
 #[derive(Debug, serde::Deserialize, PartialEq)]
-struct CircleCIItem {
+struct PipelineItem {
     id: String,
+    number: u32,
 }
 
 #[derive(Debug, serde::Deserialize, PartialEq)]
@@ -20,69 +67,470 @@ struct WorkflowResponse {
 #[derive(Debug, serde::Deserialize, PartialEq)]
 struct WorkflowItem {
     id: String,
+    name: String,
     status: String,
-    pipeline_number: u32,
+    created_at: String,
+    stopped_at: Option<String>,
 }
 
-impl WorkflowItem {
-    fn to_build_status(&self, org: &str, repo: &str) -> Result<BuildStatus,String> {
-        let WorkflowItem { id, status, pipeline_number } = self;
-        let app_url = format!(
-            "https://app.circleci.com/pipelines/github/{org}/{repo}/{pipeline_number}/workflows/{id}"
-        );
+fn to_status(status: &str) -> Result<Status, Error> {
+    match status {
+        "success" => Ok(Status::Green),
+        "failed" | "failing" | "error" => Ok(Status::Red),
+        "running" => Ok(Status::Running),
+        "on_hold" => Ok(Status::Pending),
+        "canceled" | "not_run" => Ok(Status::Cancelled),
+        "unauthorized" => Err(Error::Provider(String::from("unauthorized"))),
+        _ => Ok(Status::Unknown),
+    }
+}
 
-        let status = status.as_str();
-        match status {
-            "success" => Ok(BuildStatus {
-                status: crate::build_status::Status::Green,
-                time_info: None,
-                url: app_url,
-            }),
-            "failed" | "failing" => Ok(BuildStatus {
-                status: crate::build_status::Status::Red,
-                time_info: None,
-                url: app_url,
+/// From the creation of the first workflow to the end of the last one, once
+/// they have all stopped
+fn to_time_info(items: &[WorkflowItem]) -> Option<TimeInfo> {
+    let parse = |s: &str| chrono::DateTime::parse_from_rfc3339(s).ok();
+    let mut created_at = Vec::new();
+    let mut stopped_at = Vec::new();
+    for item in items {
+        let stopped = item.stopped_at.as_deref()?;
+        created_at.push(parse(&item.created_at)?);
+        stopped_at.push((parse(stopped)?, stopped));
+    }
+    let started = created_at.into_iter().min()?;
+    let (completed, completed_at) = stopped_at.into_iter().max()?;
+    let duration_secs = (completed - started).num_seconds().max(0);
+    Some(TimeInfo {
+        completed_at: completed_at.to_string(),
+        duration_secs: Some(u32::try_from(duration_secs).unwrap_or(u32::MAX)),
+    })
+}
+
+/// Started with the creation of its first workflow
+fn to_running_info(
+    items: &[WorkflowItem],
+    now: chrono::DateTime<chrono::FixedOffset>,
+) -> Option<RunningInfo> {
+    let started_at = items
+        .iter()
+        .filter_map(|item| chrono::DateTime::parse_from_rfc3339(&item.created_at).ok())
+        .min()?;
+    Some(RunningInfo::new(started_at, now, None))
+}
+
+impl WorkflowResponse {
+    /// `None` when the pipeline has no (matching) workflow
+    fn into_build_status(
+        self,
+        project_url: &str,
+        pipeline_number: u32,
+        workflow: Option<&str>,
+        now: chrono::DateTime<chrono::FixedOffset>,
+    ) -> Result<Option<BuildStatus>, Error> {
+        let pipeline_url = format!("{project_url}/{pipeline_number}");
+        let (status, url, items) = match workflow {
+            Some(workflow) => {
+                // reruns are listed first
+                let Some(item) = self.items.into_iter().find(|item| item.name == workflow) else {
+                    return Ok(None);
+                };
+                let url = format!("{pipeline_url}/workflows/{}", item.id);
+                (to_status(&item.status)?, url, vec![item])
+            }
+            None if self.items.is_empty() => return Ok(None),
+            None => {
+                let statuses = self
+                    .items
+                    .iter()
+                    .map(|item| to_status(&item.status))
+                    .collect::<Result<Vec<_>, _>>()?;
+                (aggregate(&statuses), pipeline_url, self.items)
+            }
+        };
+        let running = match status {
+            Status::Running => to_running_info(&items, now).map(|running| RunningInfo {
+                url: Some(url.clone()),
+                ..running
             }),
-            "error" => Err(String::from("build error")),
-            _ => Err(format!("unhandled status {status}")),
-        }
+            _ => None,
+        };
+        Ok(Some(BuildStatus {
+            status,
+            url,
+            time_info: to_time_info(&items),
+            running,
+        }))
+    }
+}
 
+/// The last finished pipeline, along with the progress of a newer one still
+/// running, like the other providers. The running one when none has
+/// finished yet.
+fn last_finished(
+    pipelines: impl IntoIterator<Item = Result<BuildStatus, Error>>,
+) -> Result<BuildStatus, Error> {
+    let mut in_progress: Option<BuildStatus> = None;
+    for build_status in pipelines {
+        let build_status = build_status?;
+        if build_status.status != Status::Running {
+            return Ok(BuildStatus {
+                running: in_progress.and_then(|p| p.running),
+                ..build_status
+            });
+        }
+        in_progress.get_or_insert(build_status);
     }
+    in_progress.ok_or(Error::NoBuild)
+}
+
+fn pipelines_url(api_url: &str, slug: &str, branch: &str) -> String {
+    let branch = encode_uri_component(branch);
+    format!("{api_url}/project/{slug}/pipeline?branch={branch}")
 }
 
 pub async fn fetch(
-    org: &String,
-    repo: &String,
-    branch: &String,
+    server_url: &Option<String>,
+    vcs: &Vcs,
+    org: &str,
+    repo: &str,
+    branch: &str,
+    workflow: &Option<String>,
     token: &Option<String>,
-) -> Result<BuildStatus, String> {
-    let pipeline_url = format!("{BASE_URL}/project/github/{org}/{repo}/pipeline?branch={branch}");
+) -> Result<BuildStatus, Error> {
+    let (api_url, app_url) = base_urls(server_url);
+    let slug = format!("{}/{org}/{repo}", vcs.slug());
+    let project_url = format!("{app_url}/pipelines/{slug}");
+    let pipeline_url = pipelines_url(&api_url, &slug, branch);
     let mut headers = vec![
         (String::from("Accept"), String::from("application/json")),
-        (String::from("Content-Type"), String::from("application/json")),
+        (
+            String::from("Content-Type"),
+            String::from("application/json"),
+        ),
     ];
     if let Some(t) = token {
         headers.push((String::from("Circle-Token"), t.to_string()));
     }
 
-    crate::utils::request::<CircleCIResponse>(&pipeline_url, &headers)
-        .await
-        .and_then(|r| match r.items.into_iter().next() {
-            Some(item) => Ok(item),
-            None => Err(String::from("No CI item found"))
-        })
-        .and_then( |item| futures::executor::block_on(async { 
-            let pipeline_id = &item.id;
-            let workflow_url = format!("{BASE_URL}/pipeline/{pipeline_id}/workflow");
-            crate::utils::request::<WorkflowResponse>(&workflow_url, &headers)
-                .await
-        }))
-        .and_then(|r| {
-            let x = r.items.into_iter().next();
-            match x {
-                Some(item) => Ok(item),
-                None => Err(String::from("No workflow item found"))
-            }
-        })
-        .and_then(|workflow_item| workflow_item.to_build_status(org, repo))        
+    let pipelines = crate::utils::request::<PipelineResponse>(&pipeline_url, &headers).await?;
+    let now = chrono::Utc::now().fixed_offset();
+    // newest first, older pipelines are only needed while newer ones run
+    let mut build_statuses = Vec::new();
+    for pipeline in pipelines.items.iter().take(MAX_PIPELINES) {
+        let workflow_url = format!("{api_url}/pipeline/{}/workflow", pipeline.id);
+        let build_status = crate::utils::request::<WorkflowResponse>(&workflow_url, &headers)
+            .await
+            .and_then(|workflows| {
+                workflows.into_build_status(&project_url, pipeline.number, workflow.as_deref(), now)
+            })
+            .transpose();
+        let Some(build_status) = build_status else {
+            continue;
+        };
+        let finished = !matches!(
+            build_status,
+            Ok(BuildStatus {
+                status: Status::Running,
+                ..
+            })
+        );
+        build_statuses.push(build_status);
+        if finished {
+            break;
+        }
+    }
+    last_finished(build_statuses)
+}
+
+/// The pipelines of a branch, or one of their workflows
+#[derive(Debug, serde::Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CircleCIConfig {
+    /// For CircleCI Server, e.g. `https://circleci.example.com`
+    pub server_url: Option<String>,
+    /// `github` by default. For `circleci` (or `gitlab`) projects, `org`
+    /// and `repo` are the ids of the organization and project.
+    #[serde(default)]
+    pub vcs: Vcs,
+    pub org: String,
+    pub repo: String,
+    pub branch: String,
+    /// Workflow name, all workflows of the pipeline when omitted
+    pub workflow: Option<String>,
+    pub token: Option<String>,
+}
+
+impl Provider for CircleCIConfig {
+    fn tag(&self) -> &'static str {
+        "circleci"
+    }
+
+    fn title(&self) -> String {
+        let CircleCIConfig {
+            org, repo, branch, ..
+        } = self;
+        match &self.workflow {
+            Some(workflow) => format!("{org}/{repo}/{branch}/{workflow}"),
+            None => format!("{org}/{repo}/{branch}"),
+        }
+    }
+
+    fn web_url(&self) -> String {
+        let (_, app_url) = base_urls(&self.server_url);
+        format!(
+            "{app_url}/pipelines/{}/{}/{}?branch={}",
+            self.vcs.slug(),
+            self.org,
+            self.repo,
+            encode_uri_component(&self.branch)
+        )
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            time_info: true,
+            running: true,
+        }
+    }
+
+    fn fetch(&self) -> BoxFuture<'_, Result<BuildStatus, Error>> {
+        fetch(
+            &self.server_url,
+            &self.vcs,
+            &self.org,
+            &self.repo,
+            &self.branch,
+            &self.workflow,
+            &self.token,
+        )
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod circle_ci_tests {
+    use super::*;
+
+    const PROJECT_URL: &str = "https://app.circleci.com/pipelines/github/org/repo";
+
+    fn now() -> chrono::DateTime<chrono::FixedOffset> {
+        chrono::DateTime::parse_from_rfc3339("2025-11-07T08:10:00Z").unwrap()
+    }
+
+    fn workflow_item(name: &str, status: &str) -> WorkflowItem {
+        WorkflowItem {
+            id: format!("{name}-id"),
+            name: String::from(name),
+            status: String::from(status),
+            created_at: String::from("2025-11-07T08:00:00Z"),
+            stopped_at: match status {
+                "running" | "on_hold" => None,
+                _ => Some(String::from("2025-11-07T08:05:00Z")),
+            },
+        }
+    }
+
+    fn workflows(items: &[(&str, &str)]) -> WorkflowResponse {
+        WorkflowResponse {
+            items: items
+                .iter()
+                .map(|(name, status)| workflow_item(name, status))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn map_status() {
+        assert_eq!(to_status("success"), Ok(Status::Green));
+        assert_eq!(to_status("failing"), Ok(Status::Red));
+        assert_eq!(to_status("running"), Ok(Status::Running));
+        assert_eq!(to_status("on_hold"), Ok(Status::Pending));
+        assert_eq!(to_status("canceled"), Ok(Status::Cancelled));
+        assert_eq!(to_status("whatever"), Ok(Status::Unknown));
+        assert!(to_status("unauthorized").is_err());
+    }
+
+    #[test]
+    fn convert_pipeline() {
+        let response = workflows(&[("build", "success"), ("deploy", "failed")]);
+        let expected = BuildStatus {
+            status: Status::Red,
+            time_info: Some(TimeInfo {
+                completed_at: String::from("2025-11-07T08:05:00Z"),
+                duration_secs: Some(300),
+            }),
+            running: None,
+            url: String::from("https://app.circleci.com/pipelines/github/org/repo/12"),
+        };
+        assert_eq!(
+            response.into_build_status(PROJECT_URL, 12, None, now()),
+            Ok(Some(expected))
+        );
+        let empty = workflows(&[]);
+        assert_eq!(
+            empty.into_build_status(PROJECT_URL, 12, None, now()),
+            Ok(None)
+        );
+        let unauthorized = workflows(&[("build", "success"), ("deploy", "unauthorized")]);
+        assert!(
+            unauthorized
+                .into_build_status(PROJECT_URL, 12, None, now())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn convert_time_info() {
+        let item = |created_at: &str, stopped_at: Option<&str>| WorkflowItem {
+            id: String::from("abc"),
+            name: String::from("build"),
+            status: String::from("success"),
+            created_at: String::from(created_at),
+            stopped_at: stopped_at.map(String::from),
+        };
+        let items = vec![
+            item("2025-11-07T08:02:00Z", Some("2025-11-07T08:20:30Z")),
+            item("2025-11-07T08:00:00Z", Some("2025-11-07T08:10:00Z")),
+        ];
+        assert_eq!(
+            to_time_info(&items),
+            Some(TimeInfo {
+                completed_at: String::from("2025-11-07T08:20:30Z"),
+                duration_secs: Some(1230),
+            })
+        );
+        // not over yet
+        let items = vec![
+            item("2025-11-07T08:00:00Z", Some("2025-11-07T08:10:00Z")),
+            item("2025-11-07T08:02:00Z", None),
+        ];
+        assert_eq!(to_time_info(&items), None);
+    }
+
+    #[test]
+    fn decode_workflows() {
+        let s = "{\"next_page_token\":null,\"items\":[{\"pipeline_id\":\"5034460f\",\"id\":\"fda08377\",\"name\":\"build-and-test\",\"project_slug\":\"gh/org/repo\",\"status\":\"success\",\"started_by\":\"03987f6a\",\"pipeline_number\":25,\"created_at\":\"2025-11-07T08:00:00Z\",\"stopped_at\":\"2025-11-07T08:05:00Z\"}]}";
+        let v = serde_json::from_str::<WorkflowResponse>(s).unwrap();
+        let mut expected = workflow_item("build-and-test", "success");
+        expected.id = String::from("fda08377");
+        assert_eq!(v.items, vec![expected]);
+    }
+
+    #[test]
+    fn decode_vcs() {
+        let vcs = |s: &str| serde_json::from_str::<Vcs>(s).unwrap().slug();
+        assert_eq!(vcs("\"github\""), "github");
+        assert_eq!(vcs("\"bitbucket\""), "bitbucket");
+        assert_eq!(vcs("\"gitlab\""), "circleci");
+        assert_eq!(vcs("\"circleci\""), "circleci");
+        assert_eq!(Vcs::default(), Vcs::GitHub);
+    }
+
+    #[test]
+    fn server_urls() {
+        assert_eq!(
+            base_urls(&None),
+            (
+                String::from("https://circleci.com/api/v2"),
+                String::from("https://app.circleci.com")
+            )
+        );
+        assert_eq!(
+            base_urls(&Some(String::from("https://circleci.corp/"))),
+            (
+                String::from("https://circleci.corp/api/v2"),
+                String::from("https://circleci.corp")
+            )
+        );
+    }
+
+    #[test]
+    fn encode_branch() {
+        assert_eq!(
+            pipelines_url(BASE_URL, "github/org/repo", "feature/a&b"),
+            "https://circleci.com/api/v2/project/github/org/repo/pipeline?branch=feature%2Fa%26b"
+        );
+        let config = CircleCIConfig {
+            server_url: None,
+            vcs: Vcs::GitHub,
+            org: String::from("org"),
+            repo: String::from("repo"),
+            branch: String::from("feature/a&b"),
+            workflow: None,
+            token: None,
+        };
+        assert_eq!(
+            config.web_url(),
+            "https://app.circleci.com/pipelines/github/org/repo?branch=feature%2Fa%26b"
+        );
+    }
+
+    #[test]
+    fn convert_named_workflow() {
+        let response = || workflows(&[("build", "success"), ("deploy", "failed")]);
+        let expected = BuildStatus {
+            status: Status::Green,
+            time_info: Some(TimeInfo {
+                completed_at: String::from("2025-11-07T08:05:00Z"),
+                duration_secs: Some(300),
+            }),
+            running: None,
+            url: String::from(
+                "https://app.circleci.com/pipelines/github/org/repo/12/workflows/build-id",
+            ),
+        };
+        assert_eq!(
+            response().into_build_status(PROJECT_URL, 12, Some("build"), now()),
+            Ok(Some(expected))
+        );
+        assert_eq!(
+            response().into_build_status(PROJECT_URL, 12, Some("nightly"), now()),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn running_pipeline() {
+        let running = workflows(&[("build", "running")])
+            .into_build_status(PROJECT_URL, 13, None, now())
+            .unwrap()
+            .unwrap();
+        let expected_running = Some(RunningInfo {
+            started_at: Some(String::from("2025-11-07T08:00:00+00:00")),
+            elapsed_secs: Some(600),
+            estimated_secs: None,
+            url: Some(String::from(
+                "https://app.circleci.com/pipelines/github/org/repo/13",
+            )),
+        });
+        assert_eq!(running.running, expected_running);
+        let finished = || {
+            workflows(&[("build", "failed")])
+                .into_build_status(PROJECT_URL, 12, None, now())
+                .unwrap()
+                .unwrap()
+        };
+        // the last result stays, along with the progress of the new pipeline
+        let build_status = last_finished(vec![Ok(running), Ok(finished())]).unwrap();
+        assert_eq!(build_status.status, Status::Red);
+        assert_eq!(
+            build_status.url,
+            "https://app.circleci.com/pipelines/github/org/repo/12"
+        );
+        assert_eq!(build_status.running, expected_running);
+        assert_eq!(last_finished(vec![Ok(finished())]), Ok(finished()));
+        // nothing finished yet
+        let running = || {
+            workflows(&[("build", "running")])
+                .into_build_status(PROJECT_URL, 13, None, now())
+                .unwrap()
+                .unwrap()
+        };
+        assert_eq!(last_finished(vec![Ok(running())]), Ok(running()));
+        assert_eq!(last_finished(vec![]), Err(Error::NoBuild));
+        // later pipelines are not needed
+        assert_eq!(
+            last_finished(vec![Ok(finished()), Err(Error::Timeout)]),
+            Ok(finished())
+        );
+    }
 }
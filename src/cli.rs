@@ -0,0 +1,160 @@
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+
+use rwatch::rendering::OutputFormat;
+
+static CONFIG_FILE: &str = ".bwatch.json";
+
+#[derive(Debug, Parser)]
+#[command(name = "rwatch", version, about = "Watch the status of your CI builds")]
+pub struct Cli {
+    /// Path to the config file [default: ~/.bwatch.json]
+    #[arg(short, long, env = "RWATCH_CONFIG", global = true)]
+    pub config: Option<PathBuf>,
+
+    /// Only show the builds of these groups (repeat or separate with commas)
+    #[arg(short, long = "group", value_delimiter = ',', global = true)]
+    pub groups: Vec<String>,
+
+    /// Print a heading per group, with the builds underneath
+    #[arg(long, global = true)]
+    pub by_group: bool,
+
+    /// Show how long ago builds completed, e.g. "12 min ago"
+    #[arg(long, global = true, conflicts_with = "absolute")]
+    pub relative: bool,
+
+    /// Show the dates builds completed, even with `display.relativeTimes`
+    #[arg(long, global = true)]
+    pub absolute: bool,
+
+    /// Time zone of the displayed times, e.g. `Europe/Paris` [default: local]
+    #[arg(long, global = true)]
+    pub time_zone: Option<String>,
+
+    /// Desktop notification when a build breaks, is fixed, or cannot be
+    /// fetched anymore (watch, tui and serve)
+    #[arg(long, global = true)]
+    pub notify: bool,
+
+    /// Output format of the build results
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Table, global = true)]
+    pub output: OutputFormat,
+
+    /// Address of the dashboard (serve)
+    #[arg(
+        long,
+        env = "RWATCH_LISTEN",
+        default_value = "127.0.0.1:8080",
+        global = true
+    )]
+    pub listen: String,
+
+    /// Print nothing, only report the result through the exit code :
+    /// 0 when all builds are green, 1 when a build is red or unstable,
+    /// 2 on errors, 3 when builds are running, pending or cancelled. With
+    /// watch, only the notifications and hooks remain. The tui still draws.
+    #[arg(short, long, global = true)]
+    pub quiet: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Subcommand)]
+pub enum Command {
+    /// Fetch all builds once and print their status (default)
+    Status,
+    /// Poll the builds at the configured interval and redraw the table
+    Watch,
+    /// Full-screen interactive UI, polling the builds at the configured interval
+    Tui,
+    /// Serve a dashboard page, `/api/status` and `/cctray.xml`, polling the
+    /// builds at the configured interval
+    Serve,
+    /// Print the configured builds without fetching them
+    List,
+    /// Parse the config file and report problems
+    Validate,
+}
+
+impl Cli {
+    pub fn config_file(&self) -> Result<PathBuf, String> {
+        match &self.config {
+            Some(path) => Ok(path.clone()),
+            None => std::env::home_dir()
+                .map(|home| home.join(CONFIG_FILE))
+                .ok_or_else(|| String::from("Cannot find home directory, use --config")),
+        }
+    }
+
+    pub fn command(&self) -> Command {
+        self.command.unwrap_or(Command::Status)
+    }
+
+    /// `None` when neither `--relative` nor `--absolute` is given, the config
+    /// decides then
+    pub fn relative_times(&self) -> Option<bool> {
+        match (self.relative, self.absolute) {
+            (true, _) => Some(true),
+            (_, true) => Some(false),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod cli_tests {
+    use super::*;
+
+    #[test]
+    fn parse_args() {
+        let cli = Cli::try_parse_from([
+            "rwatch", "--config", "my.json", "watch", "-g", "g1,g2", "--group", "g3",
+        ])
+        .unwrap();
+        assert_eq!(cli.config, Some(PathBuf::from("my.json")));
+        assert_eq!(cli.command(), Command::Watch);
+        assert_eq!(cli.groups, vec!["g1", "g2", "g3"]);
+        assert!(!cli.by_group);
+        assert_eq!(cli.output, OutputFormat::Table);
+        assert!(!cli.relative);
+        assert_eq!(cli.time_zone, None);
+    }
+
+    #[test]
+    fn time_options() {
+        let cli = Cli::try_parse_from([
+            "rwatch",
+            "watch",
+            "--relative",
+            "--time-zone",
+            "Europe/Paris",
+        ])
+        .unwrap();
+        assert_eq!(cli.relative_times(), Some(true));
+        assert!(!cli.notify);
+        assert_eq!(cli.time_zone.as_deref(), Some("Europe/Paris"));
+    }
+
+    #[test]
+    fn absolute_times() {
+        let cli = Cli::try_parse_from(["rwatch", "watch", "--absolute"]).unwrap();
+        assert_eq!(cli.relative_times(), Some(false));
+        let cli = Cli::try_parse_from(["rwatch", "watch"]).unwrap();
+        assert_eq!(cli.relative_times(), None);
+        assert!(Cli::try_parse_from(["rwatch", "--relative", "--absolute"]).is_err());
+    }
+
+    #[test]
+    fn default_command() {
+        let cli = Cli::try_parse_from(["rwatch", "-c", "my.json", "-o", "json"]).unwrap();
+        assert_eq!(cli.command(), Command::Status);
+        assert_eq!(cli.config_file(), Ok(PathBuf::from("my.json")));
+        assert_eq!(cli.output, OutputFormat::Json);
+        assert!(!cli.quiet);
+        let cli = Cli::try_parse_from(["rwatch", "status", "-o", "cctray"]).unwrap();
+        assert_eq!(cli.output, OutputFormat::CCTray);
+    }
+}
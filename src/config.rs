@@ -1,16 +1,168 @@
-use crate::{bamboo, build_status::BuildStatus, circle_ci, jenkins, travis};
+use crate::{
+    bamboo, build_status::BuildStatus, cctray, circle_ci, custom, error::Error, exec,
+    github_actions, gitlab, jenkins, provider::Provider, travis,
+};
+use futures::StreamExt;
 use regex::Regex;
+use std::{collections::HashMap, time::Duration};
 
 #[derive(Debug, serde::Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Config {
+    pub polling_interval: Option<u64>,
+    #[serde(default)]
+    pub http: HttpConfig,
+    #[serde(default)]
+    pub display: DisplayConfig,
+    /// Commands run when a build changes state (watch, tui and serve)
+    #[serde(default)]
+    pub hooks: Vec<HookConfig>,
     pub builds: Vec<BuildConfig>,
 }
 
+#[derive(Debug, Default, serde::Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpConfig {
+    /// Timeout of each request, none by default
+    pub timeout_secs: Option<u64>,
+    /// Proxy for all requests, e.g. `http://proxy.corp:3128`. When omitted,
+    /// the `HTTPS_PROXY` / `HTTP_PROXY` / `NO_PROXY` env variables are used.
+    pub proxy: Option<String>,
+    /// Comma separated hosts to reach without the proxy, defaults to `NO_PROXY`
+    pub no_proxy: Option<String>,
+    /// Paths to PEM files with extra CA certificates to trust
+    #[serde(default)]
+    pub ca_certificates: Vec<String>,
+    /// Servers for which invalid, e.g. self-signed, certificates are accepted.
+    /// Scheme, host and port must match exactly, a path restricts it further.
+    #[serde(default)]
+    pub accept_invalid_certs: Vec<String>,
+    /// Retries of failed requests
+    #[serde(default)]
+    pub retry: RetryConfig,
+    /// Builds fetched at the same time, 16 by default
+    pub max_concurrent_builds: Option<usize>,
+    /// Requests in flight to the same server, 4 by default
+    pub max_requests_per_server: Option<usize>,
+    /// Limits of specific servers, e.g. `https://my.jenkins:8443`, overriding
+    /// `maxRequestsPerServer`
+    #[serde(default)]
+    pub server_limits: HashMap<String, usize>,
+}
+
+#[derive(Debug, Default, serde::Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DisplayConfig {
+    /// IANA time zone of the displayed times, e.g. `Europe/Paris`, local
+    /// time by default
+    pub time_zone: Option<String>,
+    /// Show how long ago builds completed, e.g. "12 min ago", rather than
+    /// the date
+    #[serde(default)]
+    pub relative_times: bool,
+    /// Highlight the builds whose last result is older than this
+    pub stale_after_hours: Option<u64>,
+}
+
+/// States a hook can be restricted to
+pub const HOOK_STATES: [&str; 4] = ["green", "red", "unstable", "failing"];
+
+#[derive(Debug, serde::Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HookConfig {
+    /// Run with `sh -c`, the `RWATCH_*` env variables describe the build
+    pub command: String,
+    /// Only for the builds of these groups
+    #[serde(default)]
+    pub groups: Vec<String>,
+    /// Only for these builds, by title. A hook without groups nor builds is
+    /// run for all builds.
+    #[serde(default)]
+    pub builds: Vec<String>,
+    /// Only when builds become one of `green`, `red`, `unstable`, `failing`
+    #[serde(default)]
+    pub on: Vec<String>,
+    /// 30 seconds by default, the command is killed after that
+    pub timeout_secs: Option<u64>,
+}
+
+impl HookConfig {
+    pub fn applies_to(&self, build: &BuildConfig, state: &str) -> bool {
+        let for_build = (self.groups.is_empty() && self.builds.is_empty())
+            || build.is_in_any_group(&self.groups)
+            || self.builds.contains(&build.get_title());
+        for_build && (self.on.is_empty() || self.on.iter().any(|s| s == state))
+    }
+}
+
+pub fn parse_time_zone(s: &str) -> Result<chrono_tz::Tz, Error> {
+    s.parse::<chrono_tz::Tz>()
+        .map_err(|_| Error::Config(format!("Unknown time zone {s}")))
+}
+
+pub const DEFAULT_MAX_CONCURRENT_BUILDS: usize = 16;
+pub const DEFAULT_MAX_REQUESTS_PER_SERVER: usize = 4;
+
+/// Connection errors, timeouts, 5xx and 429 responses are retried with an
+/// exponential backoff, randomized to avoid retrying all builds at once
+#[derive(Debug, Clone, serde::Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RetryConfig {
+    /// Total number of attempts, `1` disables retries
+    pub attempts: u32,
+    /// Delay before the first retry, doubled on each following one
+    pub initial_backoff_ms: u64,
+    /// Upper bound of the delay. A server asking to wait longer than this
+    /// (`Retry-After`) is not retried, and is left alone until then.
+    pub max_backoff_ms: u64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        RetryConfig {
+            attempts: 3,
+            initial_backoff_ms: 500,
+            max_backoff_ms: 30000,
+        }
+    }
+}
+
+impl Config {
+    /// Distinct group names, in order of first appearance
+    pub fn group_names(&self) -> Vec<String> {
+        let mut res: Vec<String> = Vec::new();
+        for group in self.builds.iter().flat_map(|b| b.get_groups()) {
+            if !res.contains(group) {
+                res.push(group.to_string());
+            }
+        }
+        res
+    }
+
+    pub fn retain_groups(&mut self, groups: &[String]) {
+        self.builds.retain(|b| b.is_in_any_group(groups));
+    }
+
+    /// Fetch all builds, a few at a time, keeping the order of the config
+    pub async fn fetch_all(&self) -> Vec<Result<BuildStatus, Error>> {
+        // 0 is rejected by `load_config`, not when the config is built in code
+        let max_concurrent_builds = self
+            .http
+            .max_concurrent_builds
+            .unwrap_or(DEFAULT_MAX_CONCURRENT_BUILDS)
+            .max(1);
+        futures::stream::iter(self.builds.iter().map(BuildConfig::fetch))
+            .buffered(max_concurrent_builds)
+            .collect()
+            .await
+    }
+}
+
 const PREFIX_LEN: usize = "${process.env.".len();
+const VARIABLE_PATTERN: &str = r"\$\{process\.env\.[a-zA-Z_]*\}";
 
 fn substitute_variables(s: &String, replacer: fn(&str) -> Option<String>) -> String {
-    let re = Regex::new(r"\$\{process\.env\.[a-zA-Z_]*\}").unwrap();
+    let re = Regex::new(VARIABLE_PATTERN).unwrap();
     let matches = re.find_iter(s); //.collect::<Vec<_>>();
     let mut res = String::from("");
     let mut index = 0;
@@ -29,107 +181,205 @@ fn substitute_variables(s: &String, replacer: fn(&str) -> Option<String>) -> Str
     res
 }
 
+fn variable_names(s: &str) -> Vec<String> {
+    let re = Regex::new(VARIABLE_PATTERN).unwrap();
+    let mut res: Vec<String> = Vec::new();
+    for m in re.find_iter(s) {
+        let m_str = m.as_str();
+        let var_name = &m_str[PREFIX_LEN..m_str.len() - 1];
+        if !res.iter().any(|n| n == var_name) {
+            res.push(var_name.to_string());
+        }
+    }
+    res
+}
+
 pub fn env_replacer(name: &str) -> Option<String> {
     std::env::var(name).ok()
 }
 
-pub fn load_config(s: &String, replacer: fn(&str) -> Option<String>) -> Result<Config, String> {
+/// Values that would make the polling hang or spin, rejected when loading
+/// the config
+fn unusable_values(config: &Config) -> Vec<String> {
+    let mut problems: Vec<String> = Vec::new();
+    if config.polling_interval == Some(0) {
+        problems.push(String::from("pollingInterval must be greater than 0"));
+    }
+    if config.http.max_concurrent_builds == Some(0) {
+        problems.push(String::from(
+            "http.maxConcurrentBuilds must be greater than 0",
+        ));
+    }
+    if config.http.max_requests_per_server == Some(0) {
+        problems.push(String::from(
+            "http.maxRequestsPerServer must be greater than 0",
+        ));
+    }
+    let mut servers: Vec<&String> = config.http.server_limits.keys().collect();
+    servers.sort();
+    for server in servers {
+        if config.http.server_limits[server] == 0 {
+            problems.push(format!(
+                "http.serverLimits of {server} must be greater than 0"
+            ));
+        }
+    }
+    problems
+}
+
+fn invalid_builds(config: &Config) -> Vec<String> {
+    config
+        .builds
+        .iter()
+        .flat_map(|build| build.provider().validate())
+        .collect()
+}
+
+fn parse_config(s: &String, replacer: fn(&str) -> Option<String>) -> Result<Config, Error> {
     let sub = substitute_variables(s, replacer);
-    serde_json::from_str::<Config>(&sub).map_err(|e| format!("JSON Error {:?}", e))
+    serde_json::from_str::<Config>(&sub).map_err(|e| Error::Config(e.to_string()))
+}
+
+pub fn load_config(s: &String, replacer: fn(&str) -> Option<String>) -> Result<Config, Error> {
+    let config = parse_config(s, replacer)?;
+    let mut problems = unusable_values(&config);
+    problems.extend(invalid_builds(&config));
+    if problems.is_empty() {
+        Ok(config)
+    } else {
+        Err(Error::Config(problems.join(", ")))
+    }
+}
+
+/// Report the problems found in the config : invalid JSON, undefined
+/// environment variables, duplicate builds...
+pub fn validate_config(s: &String, replacer: fn(&str) -> Option<String>) -> Vec<String> {
+    let mut problems: Vec<String> = Vec::new();
+    for var_name in variable_names(s) {
+        if replacer(&var_name).is_none() {
+            problems.push(format!("Environment variable {var_name} is not defined"));
+        }
+    }
+    match parse_config(s, replacer) {
+        Ok(config) => {
+            if config.http.retry.attempts == 0 {
+                problems.push(String::from("http.retry.attempts must be greater than 0"));
+            }
+            problems.extend(unusable_values(&config));
+            if let Some(time_zone) = &config.display.time_zone
+                && parse_time_zone(time_zone).is_err()
+            {
+                problems.push(format!("Unknown time zone {time_zone}"));
+            }
+            for hook in config.hooks.iter() {
+                for state in hook.on.iter() {
+                    if !HOOK_STATES.contains(&state.as_str()) {
+                        problems.push(format!(
+                            "Unknown state {state} in hook {}, expected one of {}",
+                            hook.command,
+                            HOOK_STATES.join(", ")
+                        ));
+                    }
+                }
+            }
+            if let Err(e) = crate::utils::check_http_config(&config.http) {
+                problems.push(e.to_string());
+            }
+            if config.builds.is_empty() {
+                problems.push(String::from("No builds configured"));
+            }
+            problems.extend(invalid_builds(&config));
+            let mut titles: Vec<String> = Vec::new();
+            for build in config.builds.iter() {
+                let title = build.get_title();
+                if titles.contains(&title) {
+                    problems.push(format!("Duplicate build {title}"));
+                } else {
+                    titles.push(title);
+                }
+            }
+        }
+        Err(e) => problems.push(e.to_string()),
+    }
+    problems
+}
+
+/// A build to watch, on any provider
+#[derive(Debug, serde::Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildConfig {
+    #[serde(flatten)]
+    pub provider: ProviderConfig,
+    /// Overall deadline for fetching this build, retries included, instead
+    /// of the default timeout of `exec` commands. Each request is still
+    /// bound by `http.timeoutSecs`.
+    pub timeout_secs: Option<u64>,
+    #[serde(default)]
+    pub groups: Vec<String>,
 }
 
+/// The provider specific part of a build, selected by its `tag`
 #[derive(Debug, serde::Deserialize, PartialEq)]
-#[serde(rename_all = "lowercase", rename_all_fields = "camelCase", tag = "tag")]
-pub enum BuildConfig {
-    Bamboo {
-        server_url: String,
-        plan: String,
-        token: Option<String>,
-    },
-    CircleCI {
-        org: String,
-        repo: String,
-        branch: String,
-        token: Option<String>,
-    },
-    Travis {
-        server_url: String,
-        repository: String,
-        branch: String,
-        token: Option<String>,
-    },
-    Jenkins {
-        server_url: String,
-        plan: String,
-        branch: String,
-        user: Option<String>,
-        token: Option<String>,
-    },
+#[serde(rename_all = "lowercase", tag = "tag")]
+pub enum ProviderConfig {
+    Bamboo(bamboo::BambooConfig),
+    CircleCI(circle_ci::CircleCIConfig),
+    Travis(travis::TravisConfig),
+    Jenkins(jenkins::JenkinsConfig),
+    GitHubActions(github_actions::GitHubActionsConfig),
+    GitLab(gitlab::GitLabConfig),
+    Custom(custom::CustomConfig),
+    Exec(exec::ExecConfig),
+    CCTray(cctray::CCTrayConfig),
 }
 
 impl BuildConfig {
-    pub async fn fetch(&self) -> Result<BuildStatus, String> {
-        match self {
-            Self::Bamboo {
-                server_url,
-                plan,
-                token,
-            } => bamboo::fetch(server_url, plan, token).await,
-            Self::CircleCI {
-                org,
-                repo,
-                branch,
-                token,
-            } => circle_ci::fetch(org, repo, branch, token).await,
-            Self::Travis {
-                server_url,
-                repository,
-                branch,
-                token,
-            } => travis::fetch(server_url, repository, branch, token).await,
-            Self::Jenkins {
-                server_url,
-                plan,
-                branch,
-                user,
-                token,
-            } => jenkins::fetch(server_url, plan, branch, token, user).await,
+    pub fn provider(&self) -> &dyn Provider {
+        match &self.provider {
+            ProviderConfig::Bamboo(p) => p,
+            ProviderConfig::CircleCI(p) => p,
+            ProviderConfig::Travis(p) => p,
+            ProviderConfig::Jenkins(p) => p,
+            ProviderConfig::GitHubActions(p) => p,
+            ProviderConfig::GitLab(p) => p,
+            ProviderConfig::Custom(p) => p,
+            ProviderConfig::Exec(p) => p,
+            ProviderConfig::CCTray(p) => p,
         }
     }
 
-    pub fn get_title(&self) -> String {
-        match self {
-            Self::Bamboo {
-                server_url: _,
-                plan,
-                token: _,
-            } => plan.to_string(),
-            Self::CircleCI {
-                org,
-                repo,
-                branch,
-                token: _,
-            } => format!("{org}/{repo}/{branch}"),
-            Self::Travis {
-                server_url: _,
-                repository,
-                branch,
-                token: _,
-            } => format!("{repository}/{branch}"),
-            Self::Jenkins {
-                server_url: _,
-                plan,
-                branch,
-                user: _,
-                token: _,
-            } => format!("{plan}/{branch}"),
+    pub async fn fetch(&self) -> Result<BuildStatus, Error> {
+        let provider = self.provider();
+        let fetch = provider.fetch();
+        match self.timeout_secs.or(provider.default_timeout_secs()) {
+            Some(secs) => tokio::time::timeout(Duration::from_secs(secs), fetch)
+                .await
+                .unwrap_or(Err(Error::Timeout)),
+            None => fetch.await,
         }
     }
+
+    pub fn get_tag(&self) -> &'static str {
+        self.provider().tag()
+    }
+
+    pub fn get_groups(&self) -> &[String] {
+        &self.groups
+    }
+
+    pub fn is_in_any_group(&self, groups: &[String]) -> bool {
+        self.groups.iter().any(|g| groups.contains(g))
+    }
+
+    pub fn get_title(&self) -> String {
+        self.provider().title()
+    }
 }
 
 #[cfg(test)]
 mod config_tests {
     use super::*;
+    use crate::build_status::Status;
 
     #[test]
     fn decode_jenkins() {
@@ -138,17 +388,173 @@ mod config_tests {
         );
         let config = serde_json::from_str::<Config>(&config).unwrap();
         let expected = Config {
-            builds: vec![BuildConfig::Jenkins {
-                server_url: String::from("https://my.jenkins"),
-                plan: String::from("my-plan"),
-                branch: String::from("main"),
-                user: Some(String::from("${process.env.JENKINS_USER}")),
-                token: Some(String::from("${process.env.JENKINS_TOKEN}")),
+            polling_interval: Some(60000),
+            http: HttpConfig::default(),
+            display: DisplayConfig::default(),
+            hooks: vec![],
+            builds: vec![BuildConfig {
+                provider: ProviderConfig::Jenkins(jenkins::JenkinsConfig {
+                    server_url: String::from("https://my.jenkins"),
+                    plan: String::from("my-plan"),
+                    branch: String::from("main"),
+                    user: Some(String::from("${process.env.JENKINS_USER}")),
+                    token: Some(String::from("${process.env.JENKINS_TOKEN}")),
+                }),
+                timeout_secs: None,
+                groups: vec![],
             }],
         };
         assert_eq!(config, expected)
     }
 
+    #[test]
+    fn decode_github_actions() {
+        let config = String::from(
+            "{\"builds\":[{\"tag\":\"githubactions\",\"owner\":\"vankeisb\",\"repo\":\"r-watch\",\"branch\":\"main\",\"workflow\":\"ci.yml\",\"token\":\"${process.env.GITHUB_TOKEN}\",\"apiUrl\":\"https://github.example.com/api/v3\",\"groups\":[\"g1\"]}]}",
+        );
+        let config = serde_json::from_str::<Config>(&config).unwrap();
+        let expected = Config {
+            polling_interval: None,
+            http: HttpConfig::default(),
+            display: DisplayConfig::default(),
+            hooks: vec![],
+            builds: vec![BuildConfig {
+                provider: ProviderConfig::GitHubActions(github_actions::GitHubActionsConfig {
+                    owner: String::from("vankeisb"),
+                    repo: String::from("r-watch"),
+                    branch: String::from("main"),
+                    workflow: Some(String::from("ci.yml")),
+                    token: Some(String::from("${process.env.GITHUB_TOKEN}")),
+                    api_url: Some(String::from("https://github.example.com/api/v3")),
+                }),
+                timeout_secs: None,
+                groups: vec![String::from("g1")],
+            }],
+        };
+        assert_eq!(config, expected);
+        assert_eq!(config.builds[0].get_title(), "vankeisb/r-watch/main/ci.yml");
+    }
+
+    #[test]
+    fn decode_gitlab() {
+        let config = String::from(
+            "{\"builds\":[{\"tag\":\"gitlab\",\"serverUrl\":\"https://gitlab.example.com\",\"project\":\"my/project\",\"ref\":\"main\",\"token\":\"${process.env.GITLAB_TOKEN}\"}]}",
+        );
+        let config = serde_json::from_str::<Config>(&config).unwrap();
+        let expected = Config {
+            polling_interval: None,
+            http: HttpConfig::default(),
+            display: DisplayConfig::default(),
+            hooks: vec![],
+            builds: vec![BuildConfig {
+                provider: ProviderConfig::GitLab(gitlab::GitLabConfig {
+                    server_url: Some(String::from("https://gitlab.example.com")),
+                    project: String::from("my/project"),
+                    git_ref: String::from("main"),
+                    token: Some(String::from("${process.env.GITLAB_TOKEN}")),
+                }),
+                timeout_secs: None,
+                groups: vec![],
+            }],
+        };
+        assert_eq!(config, expected);
+    }
+
+    #[test]
+    fn decode_circle_ci() {
+        let config = String::from(
+            "{\"builds\":[{\"tag\":\"circleci\",\"serverUrl\":\"https://circleci.example.com\",\"vcs\":\"bitbucket\",\"org\":\"my-org\",\"repo\":\"my-repo\",\"branch\":\"main\",\"workflow\":\"deploy\"}]}",
+        );
+        let config = serde_json::from_str::<Config>(&config).unwrap();
+        let expected = BuildConfig {
+            provider: ProviderConfig::CircleCI(circle_ci::CircleCIConfig {
+                server_url: Some(String::from("https://circleci.example.com")),
+                vcs: circle_ci::Vcs::Bitbucket,
+                org: String::from("my-org"),
+                repo: String::from("my-repo"),
+                branch: String::from("main"),
+                workflow: Some(String::from("deploy")),
+                token: None,
+            }),
+            timeout_secs: None,
+            groups: vec![],
+        };
+        assert_eq!(config.builds, vec![expected]);
+        assert_eq!(config.builds[0].get_title(), "my-org/my-repo/main/deploy");
+    }
+
+    #[test]
+    fn decode_display() {
+        let config = String::from(
+            "{\"display\":{\"timeZone\":\"Europe/Paris\",\"relativeTimes\":true,\"staleAfterHours\":48},\"builds\":[]}",
+        );
+        let config = serde_json::from_str::<Config>(&config).unwrap();
+        let expected = DisplayConfig {
+            time_zone: Some(String::from("Europe/Paris")),
+            relative_times: true,
+            stale_after_hours: Some(48),
+        };
+        assert_eq!(config.display, expected);
+        assert_eq!(
+            parse_time_zone("Europe/Paris"),
+            Ok(chrono_tz::Europe::Paris)
+        );
+    }
+
+    #[test]
+    fn decode_hooks() {
+        let config = String::from(
+            "{\"hooks\":[{\"command\":\"lamp red\",\"groups\":[\"g1\"],\"on\":[\"red\",\"failing\"],\"timeoutSecs\":5},{\"command\":\"say hello\"}],\"builds\":[{\"tag\":\"bamboo\",\"serverUrl\":\"http://my.bamboo\",\"plan\":\"MY-PLAN\",\"groups\":[\"g1\"]},{\"tag\":\"bamboo\",\"serverUrl\":\"http://my.bamboo\",\"plan\":\"OTHER-PLAN\"}]}",
+        );
+        let config = serde_json::from_str::<Config>(&config).unwrap();
+        let expected = HookConfig {
+            command: String::from("lamp red"),
+            groups: vec![String::from("g1")],
+            builds: vec![],
+            on: vec![String::from("red"), String::from("failing")],
+            timeout_secs: Some(5),
+        };
+        assert_eq!(config.hooks[0], expected);
+        let (lamp, say) = (&config.hooks[0], &config.hooks[1]);
+        let (plan, other) = (&config.builds[0], &config.builds[1]);
+        assert!(lamp.applies_to(plan, "red"));
+        assert!(!lamp.applies_to(plan, "green"));
+        assert!(!lamp.applies_to(other, "red"));
+        assert!(say.applies_to(other, "green"));
+        let by_title = HookConfig {
+            builds: vec![String::from("OTHER-PLAN")],
+            ..expected
+        };
+        assert!(by_title.applies_to(other, "failing"));
+        // groups and builds add up
+        assert!(by_title.applies_to(plan, "failing"));
+    }
+
+    #[test]
+    fn decode_http() {
+        let config = String::from(
+            "{\"http\":{\"timeoutSecs\":20,\"proxy\":\"http://proxy.corp:3128\",\"noProxy\":\"localhost,.corp\",\"caCertificates\":[\"/etc/corp-ca.pem\"],\"acceptInvalidCerts\":[\"https://my.jenkins\"],\"retry\":{\"attempts\":5,\"maxBackoffMs\":10000},\"maxConcurrentBuilds\":8,\"maxRequestsPerServer\":2,\"serverLimits\":{\"https://my.jenkins\":1}},\"builds\":[{\"tag\":\"bamboo\",\"serverUrl\":\"http://my.bamboo\",\"plan\":\"MY-PLAN\",\"timeoutSecs\":60}]}",
+        );
+        let config = serde_json::from_str::<Config>(&config).unwrap();
+        let expected = HttpConfig {
+            timeout_secs: Some(20),
+            proxy: Some(String::from("http://proxy.corp:3128")),
+            no_proxy: Some(String::from("localhost,.corp")),
+            ca_certificates: vec![String::from("/etc/corp-ca.pem")],
+            accept_invalid_certs: vec![String::from("https://my.jenkins")],
+            retry: RetryConfig {
+                attempts: 5,
+                initial_backoff_ms: 500,
+                max_backoff_ms: 10000,
+            },
+            max_concurrent_builds: Some(8),
+            max_requests_per_server: Some(2),
+            server_limits: HashMap::from([(String::from("https://my.jenkins"), 1)]),
+        };
+        assert_eq!(config.http, expected);
+        assert_eq!(config.builds[0].timeout_secs, Some(60));
+    }
+
     #[test]
     fn decode_config() {
         let config = String::from(
@@ -156,29 +562,183 @@ mod config_tests {
         );
         let config = serde_json::from_str::<Config>(&config).unwrap();
         let expected = Config {
+            polling_interval: Some(60000),
+            http: HttpConfig::default(),
+            display: DisplayConfig::default(),
+            hooks: vec![],
             builds: vec![
-                BuildConfig::Bamboo {
-                    server_url: String::from("http://my.bamboo"),
-                    plan: String::from("MY-PLAN"),
-                    token: Some(String::from("${process.env.BAMBOO_TOKEN}")),
+                BuildConfig {
+                    provider: ProviderConfig::Bamboo(bamboo::BambooConfig {
+                        server_url: String::from("http://my.bamboo"),
+                        plan: String::from("MY-PLAN"),
+                        token: Some(String::from("${process.env.BAMBOO_TOKEN}")),
+                    }),
+                    timeout_secs: None,
+                    groups: vec![String::from("g1")],
                 },
-                BuildConfig::CircleCI {
-                    org: String::from("vankeisb"),
-                    repo: String::from("react-tea-cup"),
-                    branch: String::from("master"),
-                    token: None,
+                BuildConfig {
+                    provider: ProviderConfig::CircleCI(circle_ci::CircleCIConfig {
+                        server_url: None,
+                        vcs: circle_ci::Vcs::GitHub,
+                        org: String::from("vankeisb"),
+                        repo: String::from("react-tea-cup"),
+                        branch: String::from("master"),
+                        workflow: None,
+                        token: None,
+                    }),
+                    timeout_secs: None,
+                    groups: vec![String::from("g2")],
                 },
-                BuildConfig::Travis {
-                    server_url: String::from("https://my.travis"),
-                    repository: String::from("my/repo"),
-                    branch: String::from("develop"),
-                    token: Some(String::from("${process.env.TRAVIS_TOKEN}")),
+                BuildConfig {
+                    provider: ProviderConfig::Travis(travis::TravisConfig {
+                        server_url: String::from("https://my.travis"),
+                        repository: String::from("my/repo"),
+                        branch: String::from("develop"),
+                        token: Some(String::from("${process.env.TRAVIS_TOKEN}")),
+                    }),
+                    timeout_secs: None,
+                    groups: vec![String::from("g2")],
                 },
             ],
         };
         assert_eq!(config, expected)
     }
 
+    #[test]
+    fn groups() {
+        let config = String::from(
+            "{\"builds\":[{\"tag\":\"bamboo\",\"serverUrl\":\"http://my.bamboo\",\"plan\":\"MY-PLAN\",\"groups\":[\"g1\"]},{\"tag\":\"circleci\",\"org\":\"vankeisb\",\"repo\":\"react-tea-cup\",\"branch\":\"master\",\"groups\":[\"g2\",\"g3\"]},{\"tag\":\"travis\",\"serverUrl\":\"https://my.travis\",\"repository\":\"my/repo\",\"branch\":\"develop\"}]}",
+        );
+        let mut config = serde_json::from_str::<Config>(&config).unwrap();
+        assert_eq!(config.polling_interval, None);
+        assert_eq!(config.group_names(), vec!["g1", "g2", "g3"]);
+        config.retain_groups(&[String::from("g3")]);
+        let titles: Vec<String> = config.builds.iter().map(|b| b.get_title()).collect();
+        assert_eq!(titles, vec!["vankeisb/react-tea-cup/master"]);
+    }
+
+    #[test]
+    fn providers() {
+        let config = String::from(
+            "{\"builds\":[{\"tag\":\"bamboo\",\"serverUrl\":\"http://my.bamboo\",\"plan\":\"MY-PLAN\"},{\"tag\":\"circleci\",\"org\":\"vankeisb\",\"repo\":\"react-tea-cup\",\"branch\":\"master\"},{\"tag\":\"githubactions\",\"owner\":\"vankeisb\",\"repo\":\"r-watch\",\"branch\":\"main\",\"workflow\":\"ci.yml\",\"apiUrl\":\"https://github.example.com/api/v3\"},{\"tag\":\"gitlab\",\"project\":\"my/project\",\"ref\":\"main\"},{\"tag\":\"exec\",\"title\":\"tests\",\"command\":\"make test\",\"exitCodes\":{\"2\":\"unstable\"}}]}",
+        );
+        let config = serde_json::from_str::<Config>(&config).unwrap();
+        let providers: Vec<(&str, String)> = config
+            .builds
+            .iter()
+            .map(|b| (b.get_tag(), b.provider().web_url()))
+            .collect();
+        assert_eq!(
+            providers,
+            vec![
+                ("bamboo", String::from("http://my.bamboo/browse/MY-PLAN")),
+                (
+                    "circleci",
+                    String::from(
+                        "https://app.circleci.com/pipelines/github/vankeisb/react-tea-cup?branch=master"
+                    )
+                ),
+                (
+                    "githubactions",
+                    String::from(
+                        "https://github.example.com/vankeisb/r-watch/actions/workflows/ci.yml?query=branch%3Amain"
+                    )
+                ),
+                (
+                    "gitlab",
+                    String::from("https://gitlab.com/my/project/-/pipelines?ref=main")
+                ),
+                ("exec", String::new()),
+            ]
+        );
+        assert!(config.builds[1].provider().capabilities().running);
+        assert!(!config.builds[4].provider().capabilities().running);
+    }
+
+    #[test]
+    fn validate() {
+        let config = String::from(
+            "{\"pollingInterval\":0,\"builds\":[{\"tag\":\"bamboo\",\"serverUrl\":\"http://my.bamboo\",\"token\":\"${process.env.BAMBOO_TOKEN}\",\"plan\":\"MY-PLAN\"},{\"tag\":\"bamboo\",\"serverUrl\":\"http://my.bamboo\",\"plan\":\"MY-PLAN\"}]}",
+        );
+        assert_eq!(
+            validate_config(&config, |_| None),
+            vec![
+                "Environment variable BAMBOO_TOKEN is not defined",
+                "pollingInterval must be greater than 0",
+                "Duplicate build MY-PLAN",
+            ]
+        );
+        let invalid = String::from("{\"builds\":[{\"tag\":\"foo\"}]}");
+        assert_eq!(validate_config(&invalid, |_| None).len(), 1);
+        let limits = String::from(
+            "{\"display\":{\"timeZone\":\"Mars/Olympus_Mons\"},\"hooks\":[{\"command\":\"lamp\",\"on\":[\"purple\"]}],\"http\":{\"maxConcurrentBuilds\":0,\"serverLimits\":{\"https://my.jenkins\":0}},\"builds\":[{\"tag\":\"bamboo\",\"serverUrl\":\"http://my.bamboo\",\"plan\":\"MY-PLAN\"}]}",
+        );
+        assert_eq!(
+            validate_config(&limits, |_| None),
+            vec![
+                "http.maxConcurrentBuilds must be greater than 0",
+                "http.serverLimits of https://my.jenkins must be greater than 0",
+                "Unknown time zone Mars/Olympus_Mons",
+                "Unknown state purple in hook lamp, expected one of green, red, unstable, failing",
+            ]
+        );
+        let custom = String::from(
+            "{\"builds\":[{\"tag\":\"custom\",\"title\":\"deploy/prod\",\"url\":\"https://deploy.corp/api/prod\",\"pointers\":{\"status\":\"last.state\",\"durationSecs\":\"/last/took\"},\"statuses\":{\"ok\":\"green\"},\"groups\":[\"g1\"]},{\"tag\":\"exec\",\"title\":\"tests\",\"command\":\"make test\",\"exitCodes\":{\"two\":\"unstable\"}}]}",
+        );
+        assert_eq!(
+            validate_config(&custom, |_| None),
+            vec![
+                "Pointer durationSecs without completedAt in build deploy/prod",
+                "Invalid JSON Pointer last.state in build deploy/prod, expected e.g. /lastBuild/result",
+                "Invalid exit code two in build tests",
+            ]
+        );
+    }
+
+    #[test]
+    fn reject_zero_values() {
+        let config = String::from(
+            "{\"pollingInterval\":0,\"http\":{\"maxRequestsPerServer\":0},\"builds\":[{\"tag\":\"exec\",\"title\":\"tests\",\"command\":\"true\"}]}",
+        );
+        assert_eq!(
+            load_config(&config, |_| None),
+            Err(Error::Config(String::from(
+                "pollingInterval must be greater than 0, http.maxRequestsPerServer must be greater than 0"
+            )))
+        );
+    }
+
+    #[test]
+    fn reject_invalid_builds() {
+        let config = String::from(
+            "{\"builds\":[{\"tag\":\"cctray\",\"url\":\"https://gocd.example.com/go/cctray.xml\",\"project\":\"(my-pipeline\",\"regex\":true}]}",
+        );
+        let Err(Error::Config(problems)) = load_config(&config, |_| None) else {
+            panic!("an invalid regex is rejected");
+        };
+        assert!(problems.starts_with("invalid project regex (my-pipeline : "));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn fetch_with_zero_limit() {
+        let mut config = load_config(
+            &String::from(
+                "{\"builds\":[{\"tag\":\"exec\",\"title\":\"a\",\"command\":\"true\"},{\"tag\":\"exec\",\"title\":\"b\",\"command\":\"false\"}]}",
+            ),
+            |_| None,
+        )
+        .unwrap();
+        config.http.max_concurrent_builds = Some(0);
+        let statuses: Vec<Status> = config
+            .fetch_all()
+            .await
+            .into_iter()
+            .map(|r| r.unwrap().status)
+            .collect();
+        assert_eq!(statuses, vec![Status::Green, Status::Red]);
+    }
+
     #[test]
     fn substitute() {
         let s = String::from("foo ${process.env.YALLA}");
@@ -204,23 +764,42 @@ mod config_tests {
         );
         let config = load_config(&config, my_replacer);
         let expected = Config {
+            polling_interval: Some(60000),
+            http: HttpConfig::default(),
+            display: DisplayConfig::default(),
+            hooks: vec![],
             builds: vec![
-                BuildConfig::Bamboo {
-                    server_url: String::from("http://my.bamboo"),
-                    plan: String::from("MY-PLAN"),
-                    token: Some(String::from("btoken")),
+                BuildConfig {
+                    provider: ProviderConfig::Bamboo(bamboo::BambooConfig {
+                        server_url: String::from("http://my.bamboo"),
+                        plan: String::from("MY-PLAN"),
+                        token: Some(String::from("btoken")),
+                    }),
+                    timeout_secs: None,
+                    groups: vec![String::from("g1")],
                 },
-                BuildConfig::CircleCI {
-                    org: String::from("vankeisb"),
-                    repo: String::from("react-tea-cup"),
-                    branch: String::from("master"),
-                    token: None,
+                BuildConfig {
+                    provider: ProviderConfig::CircleCI(circle_ci::CircleCIConfig {
+                        server_url: None,
+                        vcs: circle_ci::Vcs::GitHub,
+                        org: String::from("vankeisb"),
+                        repo: String::from("react-tea-cup"),
+                        branch: String::from("master"),
+                        workflow: None,
+                        token: None,
+                    }),
+                    timeout_secs: None,
+                    groups: vec![String::from("g2")],
                 },
-                BuildConfig::Travis {
-                    server_url: String::from("https://my.travis"),
-                    repository: String::from("my/repo"),
-                    branch: String::from("develop"),
-                    token: Some(String::from("")),
+                BuildConfig {
+                    provider: ProviderConfig::Travis(travis::TravisConfig {
+                        server_url: String::from("https://my.travis"),
+                        repository: String::from("my/repo"),
+                        branch: String::from("develop"),
+                        token: Some(String::from("")),
+                    }),
+                    timeout_secs: None,
+                    groups: vec![String::from("g2")],
                 },
             ],
         };
@@ -0,0 +1,295 @@
+use std::collections::HashMap;
+
+use futures::{FutureExt, future::BoxFuture};
+use serde_json::Value;
+
+use crate::{
+    build_status::{BuildStatus, Status, TimeInfo},
+    error::Error,
+    provider::{Capabilities, Provider},
+};
+
+/// Where to find the fields of the build in the response, as JSON Pointers
+/// (RFC 6901), e.g. `/lastBuild/result`
+#[derive(Debug, Default, serde::Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Pointers {
+    pub status: String,
+    /// Link to the build, the `webUrl` of the config when omitted
+    pub url: Option<String>,
+    /// RFC 3339 date, unknown while it is `null` or missing
+    pub completed_at: Option<String>,
+    /// Number of seconds, requires `completedAt`
+    pub duration_secs: Option<String>,
+}
+
+impl Pointers {
+    pub fn all(&self) -> Vec<&String> {
+        [
+            Some(&self.status),
+            self.url.as_ref(),
+            self.completed_at.as_ref(),
+            self.duration_secs.as_ref(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+}
+
+/// Any endpoint answering the status of a build as JSON
+#[derive(Debug, serde::Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomConfig {
+    pub title: String,
+    pub url: String,
+    /// Page of the build for humans, the endpoint itself when omitted
+    pub web_url: Option<String>,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// Basic auth along with the token when set, bearer token otherwise
+    pub user: Option<String>,
+    pub token: Option<String>,
+    pub pointers: Pointers,
+    /// Values of the status field, mapped to `green`, `red`, `running`... The
+    /// values that are not mapped are `unknown`.
+    #[serde(default)]
+    pub statuses: HashMap<String, Status>,
+}
+
+fn get<'a>(response: &'a Value, pointer: &str) -> Result<&'a Value, Error> {
+    response.pointer(pointer).ok_or_else(|| Error::Payload {
+        field: Some(pointer.to_string()),
+        message: String::from("no such field"),
+    })
+}
+
+fn as_str<'a>(value: &'a Value, pointer: &str) -> Result<&'a str, Error> {
+    value.as_str().ok_or_else(|| Error::Payload {
+        field: Some(pointer.to_string()),
+        message: String::from("expected a string"),
+    })
+}
+
+/// `null` and missing fields are unknown, e.g. the end of a running build
+fn get_optional<'a>(response: &'a Value, pointer: &str) -> Option<&'a Value> {
+    response.pointer(pointer).filter(|value| !value.is_null())
+}
+
+/// Strings are mapped as is, other values by their JSON text (`true`, `0`...)
+fn to_status(value: &Value, statuses: &HashMap<String, Status>) -> Status {
+    let key = match value {
+        Value::String(s) => s.to_string(),
+        other => other.to_string(),
+    };
+    statuses.get(&key).copied().unwrap_or(Status::Unknown)
+}
+
+impl CustomConfig {
+    fn to_build_status(&self, response: &Value) -> Result<BuildStatus, Error> {
+        let pointers = &self.pointers;
+        let status = to_status(get(response, &pointers.status)?, &self.statuses);
+        let url = match &pointers.url {
+            Some(pointer) => match get(response, pointer)? {
+                Value::Null => self.web_url(),
+                value => as_str(value, pointer)?.to_string(),
+            },
+            None => self.web_url(),
+        };
+        let completed_at = match &pointers.completed_at {
+            Some(pointer) => get_optional(response, pointer)
+                .map(|value| as_str(value, pointer))
+                .transpose()?,
+            None => None,
+        };
+        let time_info = match completed_at {
+            Some(completed_at) => {
+                chrono::DateTime::parse_from_rfc3339(completed_at).map_err(|e| Error::Payload {
+                    field: pointers.completed_at.clone(),
+                    message: e.to_string(),
+                })?;
+                let duration_secs = match &pointers.duration_secs {
+                    Some(pointer) => get_optional(response, pointer)
+                        .map(|value| {
+                            value.as_f64().ok_or_else(|| Error::Payload {
+                                field: Some(pointer.to_string()),
+                                message: String::from("expected a number"),
+                            })
+                        })
+                        .transpose()?,
+                    None => None,
+                };
+                Some(TimeInfo {
+                    completed_at: completed_at.to_string(),
+                    duration_secs: duration_secs.map(|secs| secs.max(0.0) as u32),
+                })
+            }
+            None => None,
+        };
+        Ok(BuildStatus {
+            status,
+            url,
+            time_info,
+            running: None,
+        })
+    }
+}
+
+pub async fn fetch(config: &CustomConfig) -> Result<BuildStatus, Error> {
+    let mut headers: Vec<(String, String)> = config
+        .headers
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+    headers.push((String::from("Accept"), String::from("application/json")));
+    let response = match (&config.user, &config.token) {
+        (Some(user), token) => {
+            crate::utils::request_basic::<Value>(
+                &config.url,
+                &headers,
+                user.to_owned(),
+                token.to_owned(),
+            )
+            .await
+        }
+        (None, token) => {
+            if let Some(t) = token {
+                headers.push((String::from("Authorization"), format!("Bearer {t}")));
+            }
+            crate::utils::request::<Value>(&config.url, &headers).await
+        }
+    }?;
+    config.to_build_status(&response)
+}
+
+impl Provider for CustomConfig {
+    fn tag(&self) -> &'static str {
+        "custom"
+    }
+
+    fn title(&self) -> String {
+        self.title.to_string()
+    }
+
+    fn web_url(&self) -> String {
+        self.web_url.as_ref().unwrap_or(&self.url).to_string()
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            time_info: self.pointers.completed_at.is_some(),
+            running: false,
+        }
+    }
+
+    fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.pointers.duration_secs.is_some() && self.pointers.completed_at.is_none() {
+            problems.push(format!(
+                "Pointer durationSecs without completedAt in build {}",
+                self.title
+            ));
+        }
+        for pointer in self.pointers.all() {
+            if !pointer.is_empty() && !pointer.starts_with('/') {
+                problems.push(format!(
+                    "Invalid JSON Pointer {pointer} in build {}, expected e.g. /lastBuild/result",
+                    self.title
+                ));
+            }
+        }
+        problems
+    }
+
+    fn fetch(&self) -> BoxFuture<'_, Result<BuildStatus, Error>> {
+        fetch(self).boxed()
+    }
+}
+
+#[cfg(test)]
+mod custom_tests {
+    use super::*;
+
+    fn config() -> CustomConfig {
+        serde_json::from_str(
+            "{\"title\":\"deploy/prod\",\"url\":\"https://deploy.corp/api/prod\",\"webUrl\":\"https://deploy.corp/prod\",\"pointers\":{\"status\":\"/last/state\",\"url\":\"/last/link\",\"completedAt\":\"/last/finishedAt\",\"durationSecs\":\"/last/took\"},\"statuses\":{\"ok\":\"green\",\"ko\":\"red\",\"true\":\"running\"}}",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn convert() {
+        let response = serde_json::json!({
+            "last": {
+                "state": "ko",
+                "link": "https://deploy.corp/prod/42",
+                "finishedAt": "2025-11-07T09:19:46+01:00",
+                "took": 95.4
+            }
+        });
+        let expected = BuildStatus {
+            status: Status::Red,
+            url: String::from("https://deploy.corp/prod/42"),
+            time_info: Some(TimeInfo {
+                completed_at: String::from("2025-11-07T09:19:46+01:00"),
+                duration_secs: Some(95),
+            }),
+            running: None,
+        };
+        assert_eq!(config().to_build_status(&response), Ok(expected));
+    }
+
+    #[test]
+    fn map_statuses() {
+        let statuses = config().statuses;
+        assert_eq!(to_status(&Value::from("ok"), &statuses), Status::Green);
+        assert_eq!(to_status(&Value::from(true), &statuses), Status::Running);
+        assert_eq!(to_status(&Value::from("?"), &statuses), Status::Unknown);
+    }
+
+    #[test]
+    fn missing_fields() {
+        let mut config = config();
+        let response = serde_json::json!({ "last": { "state": "ok" } });
+        assert_eq!(
+            config.to_build_status(&response),
+            Err(Error::Payload {
+                field: Some(String::from("/last/link")),
+                message: String::from("no such field"),
+            })
+        );
+        config.pointers = Pointers {
+            status: String::from("/last/state"),
+            ..Pointers::default()
+        };
+        let build_status = config.to_build_status(&response).unwrap();
+        assert_eq!(build_status.status, Status::Green);
+        assert_eq!(build_status.url, "https://deploy.corp/prod");
+        assert_eq!(build_status.time_info, None);
+    }
+
+    #[test]
+    fn running_build() {
+        let response = serde_json::json!({
+            "last": { "state": true, "link": null, "finishedAt": null, "took": null }
+        });
+        let expected = BuildStatus {
+            status: Status::Running,
+            url: String::from("https://deploy.corp/prod"),
+            time_info: None,
+            running: None,
+        };
+        assert_eq!(config().to_build_status(&response), Ok(expected));
+        let response = serde_json::json!({
+            "last": { "state": "ok", "link": "https://deploy.corp/prod/42", "finishedAt": "2025-11-07T09:19:46+01:00" }
+        });
+        let time_info = config().to_build_status(&response).unwrap().time_info;
+        assert_eq!(
+            time_info,
+            Some(TimeInfo {
+                completed_at: String::from("2025-11-07T09:19:46+01:00"),
+                duration_secs: None,
+            })
+        );
+    }
+}
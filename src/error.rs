@@ -0,0 +1,188 @@
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// The request could not be sent, or the connection failed
+    Network(String),
+    /// The server did not answer in time
+    Timeout,
+    /// 401 or 403
+    Auth(u16),
+    /// 404
+    NotFound,
+    /// 429, or 503 with `Retry-After`, with the delay asked by the server if any
+    RateLimited { retry_after_secs: Option<u64> },
+    /// Any other unexpected HTTP status
+    Http(u16),
+    /// The response could not be decoded, with the path of the offending field
+    Payload {
+        field: Option<String>,
+        message: String,
+    },
+    /// The CI answered, but has no build for this config
+    NoBuild,
+    /// Error reported by the CI itself, like Travis' `error_message`
+    Provider(String),
+    /// The config could not be loaded
+    Config(String),
+    /// Local I/O failed, e.g. the terminal or the desktop notifications
+    Io(String),
+}
+
+impl Error {
+    /// Short identifier of the error, for machine readable outputs
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::Network(_) => "network",
+            Error::Timeout => "timeout",
+            Error::Auth(_) => "auth",
+            Error::NotFound => "notFound",
+            Error::RateLimited { .. } => "rateLimited",
+            Error::Http(_) => "http",
+            Error::Payload { .. } => "payload",
+            Error::NoBuild => "noBuild",
+            Error::Provider(_) => "provider",
+            Error::Config(_) => "config",
+            Error::Io(_) => "io",
+        }
+    }
+
+    /// Failures that may not happen again, and are worth retrying
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Error::Network(_) | Error::Timeout | Error::RateLimited { .. } => true,
+            Error::Http(status) => *status >= 500,
+            _ => false,
+        }
+    }
+
+    pub fn from_status(status: u16, retry_after_secs: Option<u64>) -> Error {
+        match status {
+            401 | 403 => Error::Auth(status),
+            404 => Error::NotFound,
+            429 => Error::RateLimited { retry_after_secs },
+            _ => Error::Http(status),
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            return Error::Timeout;
+        }
+        // the source has the actual cause (DNS, connection refused...)
+        let mut message = e.to_string();
+        let mut source = std::error::Error::source(&e);
+        while let Some(s) = source {
+            message = s.to_string();
+            source = s.source();
+        }
+        Error::Network(message)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e.to_string())
+    }
+}
+
+impl<E: std::fmt::Display> From<serde_path_to_error::Error<E>> for Error {
+    fn from(e: serde_path_to_error::Error<E>) -> Self {
+        let path = e.path().to_string();
+        Error::Payload {
+            // the path is "." when the root itself is invalid
+            field: if path == "." { None } else { Some(path) },
+            message: e.inner().to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Network(message) => write!(f, "network error : {message}"),
+            Error::Timeout => write!(f, "timed out"),
+            Error::Auth(status) => write!(f, "authentication failed ({status})"),
+            Error::NotFound => write!(f, "not found (404)"),
+            Error::RateLimited {
+                retry_after_secs: Some(secs),
+            } => write!(f, "rate limited, retry in {secs}s"),
+            Error::RateLimited {
+                retry_after_secs: None,
+            } => write!(f, "rate limited"),
+            Error::Http(status) => write!(f, "unexpected status {status}"),
+            Error::Payload {
+                field: Some(field),
+                message,
+            } => write!(f, "unexpected response at {field} : {message}"),
+            Error::Payload {
+                field: None,
+                message,
+            } => write!(f, "unexpected response : {message}"),
+            Error::NoBuild => write!(f, "no build found"),
+            Error::Provider(message) => write!(f, "{message}"),
+            Error::Config(message) => write!(f, "invalid config : {message}"),
+            Error::Io(message) => write!(f, "I/O error : {message}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod error_tests {
+    use super::*;
+
+    #[test]
+    fn from_status() {
+        assert_eq!(Error::from_status(401, None), Error::Auth(401));
+        assert_eq!(Error::from_status(403, None), Error::Auth(403));
+        assert_eq!(Error::from_status(404, None), Error::NotFound);
+        assert_eq!(
+            Error::from_status(429, Some(30)),
+            Error::RateLimited {
+                retry_after_secs: Some(30)
+            }
+        );
+        assert_eq!(Error::from_status(502, None), Error::Http(502));
+    }
+
+    #[test]
+    fn transient() {
+        assert!(Error::Timeout.is_transient());
+        assert!(Error::Network(String::from("connection refused")).is_transient());
+        assert!(Error::Http(502).is_transient());
+        assert!(Error::from_status(429, None).is_transient());
+        assert!(!Error::Http(400).is_transient());
+        assert!(!Error::Auth(401).is_transient());
+        assert!(!Error::NotFound.is_transient());
+        assert!(!Error::NoBuild.is_transient());
+    }
+
+    #[test]
+    fn payload_field() {
+        #[derive(Debug, serde::Deserialize)]
+        #[allow(dead_code)]
+        struct Response {
+            items: Vec<Item>,
+        }
+        #[derive(Debug, serde::Deserialize)]
+        #[allow(dead_code)]
+        struct Item {
+            id: String,
+        }
+        let json = "{\"items\":[{\"id\":\"a\"},{\"id\":12}]}";
+        let e: Error = serde_path_to_error::deserialize::<_, Response>(
+            &mut serde_json::Deserializer::from_str(json),
+        )
+        .unwrap_err()
+        .into();
+        assert_eq!(e.kind(), "payload");
+        match e {
+            Error::Payload { field, .. } => assert_eq!(field, Some(String::from("items[1].id"))),
+            _ => panic!("unexpected error {e:?}"),
+        }
+    }
+}
@@ -0,0 +1,231 @@
+use std::{collections::HashMap, process::Stdio};
+
+use futures::{FutureExt, future::BoxFuture};
+
+use crate::{
+    build_status::{BuildStatus, Status, TimeInfo},
+    error::Error,
+    provider::{Capabilities, Provider},
+    utils::ProcessGroup,
+};
+
+/// Commands may hang, e.g. waiting for a server, unlike requests they have
+/// no timeout of their own
+const DEFAULT_TIMEOUT_SECS: u64 = 60;
+
+/// A command telling the status of something, through its output or its
+/// exit code
+#[derive(Debug, serde::Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecConfig {
+    pub title: String,
+    /// Run by the shell, `sh -c` or `cmd /C`
+    pub command: String,
+    pub working_dir: Option<String>,
+    /// Link to the build, when the command does not output one
+    pub url: Option<String>,
+    /// Status of the exit codes when the command outputs no JSON. By default
+    /// 0 is `green` and anything else `red`.
+    #[serde(default)]
+    pub exit_codes: HashMap<String, Status>,
+}
+
+/// What the command may print on its stdout, e.g.
+/// `{"status":"red","url":"https://...","completedAt":"2025-11-07T09:19:46Z","durationSecs":12}`
+#[derive(Debug, serde::Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct ExecOutput {
+    status: Status,
+    url: Option<String>,
+    #[serde(alias = "completed_at")]
+    completed_at: Option<String>,
+    #[serde(alias = "duration")]
+    duration_secs: Option<u32>,
+}
+
+impl ExecConfig {
+    fn exit_code_status(&self, code: i32) -> Status {
+        match self.exit_codes.get(&code.to_string()) {
+            Some(status) => *status,
+            None if code == 0 => Status::Green,
+            None => Status::Red,
+        }
+    }
+
+    /// JSON on stdout wins over the exit code : the whole output, or its last
+    /// line after some logs. Other outputs are ignored.
+    fn to_build_status(&self, stdout: &str, code: Option<i32>) -> Result<BuildStatus, Error> {
+        let stdout = stdout.trim();
+        let json = if stdout.starts_with('{') {
+            Some(stdout)
+        } else {
+            stdout
+                .lines()
+                .next_back()
+                .map(str::trim)
+                .filter(|line| line.starts_with('{'))
+        };
+        if let Some(json) = json {
+            let output = crate::utils::parse_json::<ExecOutput>(json)?;
+            let time_info = match (output.completed_at, output.duration_secs) {
+                (Some(completed_at), Some(duration_secs)) => Some(TimeInfo {
+                    completed_at,
+                    duration_secs: Some(duration_secs),
+                }),
+                _ => None,
+            };
+            return Ok(BuildStatus {
+                status: output.status,
+                url: output.url.unwrap_or_else(|| self.web_url()),
+                time_info,
+                running: None,
+            });
+        }
+        match code {
+            Some(code) => Ok(BuildStatus {
+                status: self.exit_code_status(code),
+                url: self.web_url(),
+                time_info: None,
+                running: None,
+            }),
+            None => Err(Error::Provider(String::from(
+                "the command was killed by a signal",
+            ))),
+        }
+    }
+}
+
+/// The command and its children are killed when the returned future is
+/// dropped, e.g. on timeout
+pub async fn fetch(config: &ExecConfig) -> Result<BuildStatus, Error> {
+    let mut command = crate::utils::shell(&config.command);
+    if let Some(working_dir) = &config.working_dir {
+        command.current_dir(working_dir);
+    }
+    let cannot_run = |e: std::io::Error| Error::Io(format!("cannot run {} : {e}", config.command));
+    let child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(cannot_run)?;
+    let process_group = ProcessGroup::new(&child);
+    let output = child.wait_with_output().await.map_err(cannot_run)?;
+    process_group.disarm();
+    config.to_build_status(
+        &String::from_utf8_lossy(&output.stdout),
+        output.status.code(),
+    )
+}
+
+impl Provider for ExecConfig {
+    fn tag(&self) -> &'static str {
+        "exec"
+    }
+
+    fn title(&self) -> String {
+        self.title.to_string()
+    }
+
+    fn web_url(&self) -> String {
+        self.url.clone().unwrap_or_default()
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            time_info: true,
+            running: false,
+        }
+    }
+
+    fn default_timeout_secs(&self) -> Option<u64> {
+        Some(DEFAULT_TIMEOUT_SECS)
+    }
+
+    fn validate(&self) -> Vec<String> {
+        let mut codes: Vec<&String> = self.exit_codes.keys().collect();
+        codes.sort();
+        codes
+            .into_iter()
+            .filter(|code| code.parse::<i32>().is_err())
+            .map(|code| format!("Invalid exit code {code} in build {}", self.title))
+            .collect()
+    }
+
+    fn fetch(&self) -> BoxFuture<'_, Result<BuildStatus, Error>> {
+        fetch(self).boxed()
+    }
+}
+
+#[cfg(test)]
+mod exec_tests {
+    use super::*;
+
+    fn config() -> ExecConfig {
+        serde_json::from_str(
+            "{\"title\":\"tests\",\"command\":\"make test\",\"url\":\"file:///tmp/report.html\",\"exitCodes\":{\"2\":\"unstable\"}}",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn exit_codes() {
+        let config = config();
+        let status = |stdout: &str, code: Option<i32>| {
+            config.to_build_status(stdout, code).map(|s| s.status)
+        };
+        assert_eq!(status("", Some(0)), Ok(Status::Green));
+        assert_eq!(status("all tests passed\n", Some(1)), Ok(Status::Red));
+        assert_eq!(status("", Some(2)), Ok(Status::Unstable));
+        assert!(status("", None).is_err());
+    }
+
+    #[test]
+    fn json_output() {
+        let stdout =
+            "{\"status\":\"running\",\"completed_at\":\"2025-11-07T09:19:46Z\",\"duration\":12}\n";
+        let expected = BuildStatus {
+            status: Status::Running,
+            url: String::from("file:///tmp/report.html"),
+            time_info: Some(TimeInfo {
+                completed_at: String::from("2025-11-07T09:19:46Z"),
+                duration_secs: Some(12),
+            }),
+            running: None,
+        };
+        assert_eq!(config().to_build_status(stdout, Some(1)), Ok(expected));
+        let logs = "Checking 12 services\n{\"status\":\"red\"}\n\n";
+        assert_eq!(
+            config().to_build_status(logs, Some(0)).map(|s| s.status),
+            Ok(Status::Red)
+        );
+        let invalid = config().to_build_status("{\"status\":\"purple\"}", Some(0));
+        assert!(matches!(invalid, Err(Error::Payload { .. })));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn run_command() {
+        let mut config = config();
+        config.command = String::from("echo '{\"status\":\"green\",\"url\":\"http://my.ci/1\"}'");
+        let build_status = fetch(&config).await.unwrap();
+        assert_eq!(build_status.status, Status::Green);
+        assert_eq!(build_status.url, "http://my.ci/1");
+        config.command = String::from("exit 2");
+        assert_eq!(fetch(&config).await.map(|s| s.status), Ok(Status::Unstable));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn kill_children_on_timeout() {
+        let marker = std::env::temp_dir().join(format!("rwatch-exec-{}", std::process::id()));
+        let mut config = config();
+        // the subshell outlives `sh` unless the whole group is killed
+        config.command = format!("(sleep 1; touch {}) | cat", marker.display());
+        let timeout = std::time::Duration::from_millis(200);
+        assert!(tokio::time::timeout(timeout, fetch(&config)).await.is_err());
+        tokio::time::sleep(std::time::Duration::from_millis(1500)).await;
+        assert!(!marker.exists());
+    }
+}
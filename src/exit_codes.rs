@@ -0,0 +1,80 @@
+use rwatch::{
+    build_status::{BuildStatus, Status},
+    error::Error,
+};
+
+/// All the builds are green
+pub const SUCCESS: u8 = 0;
+/// At least one build is red
+pub const BUILD_FAILED: u8 = 1;
+/// At least one build could not be fetched, or the config could not be loaded
+pub const ERROR: u8 = 2;
+/// No build is red, but some are not green either (running, cancelled...)
+pub const NOT_GREEN: u8 = 3;
+
+/// Compute the exit code for the fetched results. Errors take precedence
+/// over red builds, as we cannot tell the status of the failed fetches.
+pub fn from_results(results: &[Result<BuildStatus, Error>]) -> u8 {
+    let mut res = SUCCESS;
+    for result in results.iter() {
+        match result {
+            Ok(build_status) => match build_status.status {
+                Status::Green => {}
+                Status::Red | Status::Unstable => res = BUILD_FAILED,
+                _ => {
+                    if res == SUCCESS {
+                        res = NOT_GREEN;
+                    }
+                }
+            },
+            Err(_) => return ERROR,
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod exit_codes_tests {
+    use super::*;
+
+    fn build_status(status: Status) -> Result<BuildStatus, Error> {
+        Ok(BuildStatus {
+            status,
+            url: String::from("http://my.ci"),
+            time_info: None,
+            running: None,
+        })
+    }
+
+    #[test]
+    fn exit_codes() {
+        assert_eq!(from_results(&[]), SUCCESS);
+        assert_eq!(
+            from_results(&[build_status(Status::Green), build_status(Status::Green)]),
+            SUCCESS
+        );
+        assert_eq!(
+            from_results(&[build_status(Status::Green), build_status(Status::Red)]),
+            BUILD_FAILED
+        );
+        assert_eq!(
+            from_results(&[build_status(Status::Running), build_status(Status::Green)]),
+            NOT_GREEN
+        );
+        assert_eq!(
+            from_results(&[
+                build_status(Status::Cancelled),
+                build_status(Status::Unstable)
+            ]),
+            BUILD_FAILED
+        );
+        assert_eq!(
+            from_results(&[
+                build_status(Status::Red),
+                Err(Error::Timeout),
+                build_status(Status::Green)
+            ]),
+            ERROR
+        );
+    }
+}
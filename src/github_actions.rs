@@ -0,0 +1,321 @@
+use futures::{FutureExt, future::BoxFuture};
+
+use crate::{
+    build_status::{BuildStatus, RunningInfo, Status, TimeInfo, aggregate},
+    error::Error,
+    provider::{Capabilities, Provider},
+    utils::encode_uri_component,
+};
+
+static BASE_URL: &str = "https://api.github.com";
+static WEB_URL: &str = "https://github.com";
+
+#[derive(Debug, serde::Deserialize, PartialEq)]
+struct WorkflowRunsResponse {
+    workflow_runs: Vec<WorkflowRun>,
+}
+
+#[derive(Debug, serde::Deserialize, PartialEq)]
+struct WorkflowRun {
+    workflow_id: u64,
+    status: String,
+    conclusion: Option<String>,
+    html_url: String,
+    run_started_at: Option<String>,
+    updated_at: String,
+}
+
+fn to_status(status: &str, conclusion: Option<&str>) -> Status {
+    match (status, conclusion) {
+        ("completed", Some("success")) => Status::Green,
+        ("completed", Some("failure" | "timed_out" | "startup_failure")) => Status::Red,
+        ("completed", Some("cancelled" | "skipped" | "stale")) => Status::Cancelled,
+        ("completed", Some("action_required")) => Status::Pending,
+        ("completed", _) => Status::Unknown,
+        ("in_progress", _) => Status::Running,
+        ("queued" | "requested" | "waiting" | "pending", _) => Status::Pending,
+        _ => Status::Unknown,
+    }
+}
+
+fn parse_time(s: &Option<String>) -> Option<chrono::DateTime<chrono::FixedOffset>> {
+    s.as_ref()
+        .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
+}
+
+impl WorkflowRun {
+    fn to_time_info(&self) -> Option<TimeInfo> {
+        let started_at = parse_time(&self.run_started_at)?;
+        let completed_at = chrono::DateTime::parse_from_rfc3339(&self.updated_at).ok()?;
+        let duration_secs = (completed_at - started_at).num_seconds().max(0);
+        Some(TimeInfo {
+            completed_at: self.updated_at.clone(),
+            duration_secs: Some(u32::try_from(duration_secs).unwrap_or(u32::MAX)),
+        })
+    }
+
+    fn into_build_status(self, running: Option<RunningInfo>) -> BuildStatus {
+        BuildStatus {
+            status: to_status(&self.status, self.conclusion.as_deref()),
+            time_info: if self.status == "completed" {
+                self.to_time_info()
+            } else {
+                None
+            },
+            url: self.html_url,
+            running,
+        }
+    }
+}
+
+impl WorkflowRunsResponse {
+    fn into_build_status(
+        self,
+        now: chrono::DateTime<chrono::FixedOffset>,
+    ) -> Result<BuildStatus, Error> {
+        // runs are sorted by most recent first
+        let running = self
+            .workflow_runs
+            .iter()
+            .find(|run| run.status == "in_progress")
+            .and_then(|run| {
+                let started_at = parse_time(&run.run_started_at)?;
+                Some(RunningInfo {
+                    url: Some(run.html_url.clone()),
+                    ..RunningInfo::new(started_at, now, None)
+                })
+            });
+        // the last completed run of each workflow, so that a green lint run
+        // does not hide a red CI run
+        let mut completed: Vec<WorkflowRun> = Vec::new();
+        let mut most_recent: Option<WorkflowRun> = None;
+        for run in self.workflow_runs {
+            if run.status != "completed" {
+                most_recent.get_or_insert(run);
+            } else if !completed.iter().any(|c| c.workflow_id == run.workflow_id) {
+                completed.push(run);
+            }
+        }
+        let statuses: Vec<Status> = completed
+            .iter()
+            .map(|run| to_status(&run.status, run.conclusion.as_deref()))
+            .collect();
+        let status = aggregate(&statuses);
+        // link to the run that gives its status to the whole, fall back to
+        // the most recent run if none has completed yet
+        let run = completed
+            .into_iter()
+            .zip(statuses)
+            .find(|(_, s)| *s == status)
+            .map(|(run, _)| run)
+            .or(most_recent);
+        match run {
+            Some(run) => Ok(run.into_build_status(running)),
+            None => Err(Error::NoBuild),
+        }
+    }
+}
+
+/// The branch is encoded, names like `fix/a&b` are valid
+fn runs_url(
+    api_url: &str,
+    owner: &str,
+    repo: &str,
+    branch: &str,
+    workflow: &Option<String>,
+) -> String {
+    let runs_url = match workflow {
+        Some(workflow) => {
+            format!("{api_url}/repos/{owner}/{repo}/actions/workflows/{workflow}/runs")
+        }
+        None => format!("{api_url}/repos/{owner}/{repo}/actions/runs"),
+    };
+    let branch = encode_uri_component(branch);
+    format!("{runs_url}?branch={branch}&per_page=20")
+}
+
+pub async fn fetch(
+    owner: &str,
+    repo: &str,
+    branch: &str,
+    workflow: &Option<String>,
+    token: &Option<String>,
+    api_url: &Option<String>,
+) -> Result<BuildStatus, Error> {
+    let api_url = api_url.as_deref().unwrap_or(BASE_URL);
+    let url = runs_url(api_url, owner, repo, branch, workflow);
+    let mut headers = vec![
+        (
+            String::from("Accept"),
+            String::from("application/vnd.github+json"),
+        ),
+        (
+            String::from("X-GitHub-Api-Version"),
+            String::from("2022-11-28"),
+        ),
+        // the GitHub API rejects requests without a user agent
+        (String::from("User-Agent"), String::from("rwatch")),
+    ];
+    if let Some(t) = token {
+        headers.push((String::from("Authorization"), format!("Bearer {t}")));
+    }
+    crate::utils::request::<WorkflowRunsResponse>(&url, &headers)
+        .await
+        .and_then(|response| response.into_build_status(chrono::Utc::now().fixed_offset()))
+}
+
+/// The workflow runs of a branch, or of one of its workflows
+#[derive(Debug, serde::Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GitHubActionsConfig {
+    pub owner: String,
+    pub repo: String,
+    pub branch: String,
+    /// Workflow file name (e.g. `ci.yml`). When omitted, the last runs of
+    /// all workflows are aggregated.
+    pub workflow: Option<String>,
+    pub token: Option<String>,
+    /// For GitHub Enterprise, e.g. `https://github.example.com/api/v3`
+    pub api_url: Option<String>,
+}
+
+impl Provider for GitHubActionsConfig {
+    fn tag(&self) -> &'static str {
+        "githubactions"
+    }
+
+    fn title(&self) -> String {
+        let GitHubActionsConfig {
+            owner,
+            repo,
+            branch,
+            ..
+        } = self;
+        match &self.workflow {
+            Some(workflow) => format!("{owner}/{repo}/{branch}/{workflow}"),
+            None => format!("{owner}/{repo}/{branch}"),
+        }
+    }
+
+    fn web_url(&self) -> String {
+        // the API of GitHub Enterprise is served under the web app
+        let web_url = match &self.api_url {
+            Some(api_url) => api_url.trim_end_matches('/').trim_end_matches("/api/v3"),
+            None => WEB_URL,
+        };
+        let actions_url = format!("{web_url}/{}/{}/actions", self.owner, self.repo);
+        let branch = encode_uri_component(&self.branch);
+        match &self.workflow {
+            Some(workflow) => {
+                format!("{actions_url}/workflows/{workflow}?query=branch%3A{branch}")
+            }
+            None => format!("{actions_url}?query=branch%3A{branch}"),
+        }
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            time_info: true,
+            running: true,
+        }
+    }
+
+    fn fetch(&self) -> BoxFuture<'_, Result<BuildStatus, Error>> {
+        fetch(
+            &self.owner,
+            &self.repo,
+            &self.branch,
+            &self.workflow,
+            &self.token,
+            &self.api_url,
+        )
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod github_actions_tests {
+    use super::*;
+
+    #[test]
+    fn encode_branch() {
+        assert_eq!(
+            runs_url(BASE_URL, "owner", "repo", "fix/a&b#1+2", &None),
+            "https://api.github.com/repos/owner/repo/actions/runs?branch=fix%2Fa%26b%231%2B2&per_page=20"
+        );
+    }
+
+    #[test]
+    fn map_status() {
+        assert_eq!(to_status("completed", Some("success")), Status::Green);
+        assert_eq!(to_status("completed", Some("timed_out")), Status::Red);
+        assert_eq!(to_status("completed", Some("cancelled")), Status::Cancelled);
+        assert_eq!(to_status("completed", Some("neutral")), Status::Unknown);
+        assert_eq!(to_status("in_progress", None), Status::Running);
+        assert_eq!(to_status("queued", None), Status::Pending);
+    }
+
+    #[test]
+    fn decode_and_convert() {
+        let s = String::from(
+            "{\"total_count\":2,\"workflow_runs\":[{\"id\":2,\"workflow_id\":7,\"name\":\"CI\",\"head_branch\":\"main\",\"status\":\"in_progress\",\"conclusion\":null,\"html_url\":\"https://github.com/owner/repo/actions/runs/2\",\"created_at\":\"2025-11-07T08:00:00Z\",\"updated_at\":\"2025-11-07T08:01:00Z\",\"run_started_at\":\"2025-11-07T08:00:00Z\"},{\"id\":1,\"workflow_id\":7,\"name\":\"CI\",\"head_branch\":\"main\",\"status\":\"completed\",\"conclusion\":\"failure\",\"html_url\":\"https://github.com/owner/repo/actions/runs/1\",\"created_at\":\"2025-11-07T07:00:00Z\",\"updated_at\":\"2025-11-07T07:12:30Z\",\"run_started_at\":\"2025-11-07T07:00:00Z\"}]}",
+        );
+        let response = serde_json::from_str::<WorkflowRunsResponse>(&s).unwrap();
+        let now = chrono::DateTime::parse_from_rfc3339("2025-11-07T08:05:00Z").unwrap();
+        let expected = BuildStatus {
+            status: Status::Red,
+            url: String::from("https://github.com/owner/repo/actions/runs/1"),
+            time_info: Some(TimeInfo {
+                completed_at: String::from("2025-11-07T07:12:30Z"),
+                duration_secs: Some(750),
+            }),
+            running: Some(RunningInfo {
+                started_at: Some(String::from("2025-11-07T08:00:00+00:00")),
+                elapsed_secs: Some(300),
+                estimated_secs: None,
+                url: Some(String::from("https://github.com/owner/repo/actions/runs/2")),
+            }),
+        };
+        assert_eq!(response.into_build_status(now), Ok(expected));
+    }
+
+    #[test]
+    fn aggregate_workflows() {
+        let run = |id: u32, workflow_id: u64, conclusion: &str| WorkflowRun {
+            workflow_id,
+            status: String::from("completed"),
+            conclusion: Some(String::from(conclusion)),
+            html_url: format!("https://github.com/owner/repo/actions/runs/{id}"),
+            run_started_at: Some(String::from("2025-11-07T07:00:00Z")),
+            updated_at: String::from("2025-11-07T07:10:00Z"),
+        };
+        let now = chrono::DateTime::parse_from_rfc3339("2025-11-07T08:05:00Z").unwrap();
+        // lint ran after the failed CI, and CI failed after an older success
+        let response = WorkflowRunsResponse {
+            workflow_runs: vec![
+                run(3, 8, "success"),
+                run(2, 7, "failure"),
+                run(1, 7, "success"),
+            ],
+        };
+        let build_status = response.into_build_status(now).unwrap();
+        assert_eq!(build_status.status, Status::Red);
+        assert_eq!(
+            build_status.url,
+            "https://github.com/owner/repo/actions/runs/2"
+        );
+        let response = WorkflowRunsResponse {
+            workflow_runs: vec![
+                run(3, 8, "success"),
+                run(2, 7, "success"),
+                run(1, 7, "failure"),
+            ],
+        };
+        let build_status = response.into_build_status(now).unwrap();
+        assert_eq!(build_status.status, Status::Green);
+        assert_eq!(
+            build_status.url,
+            "https://github.com/owner/repo/actions/runs/3"
+        );
+    }
+}
@@ -0,0 +1,224 @@
+use futures::{FutureExt, future::BoxFuture};
+
+use crate::{
+    build_status::{BuildStatus, RunningInfo, Status, TimeInfo},
+    error::Error,
+    provider::{Capabilities, Provider},
+    utils::encode_uri_component,
+};
+
+static BASE_URL: &str = "https://gitlab.com";
+
+#[derive(Debug, serde::Deserialize, PartialEq)]
+struct PipelineItem {
+    id: u64,
+    status: String,
+    web_url: String,
+    created_at: String,
+}
+
+#[derive(Debug, serde::Deserialize, PartialEq)]
+struct PipelineDetails {
+    status: String,
+    web_url: String,
+    finished_at: Option<String>,
+    duration: Option<u32>,
+}
+
+fn to_status(status: &str) -> Status {
+    match status {
+        "success" => Status::Green,
+        "failed" => Status::Red,
+        "running" => Status::Running,
+        "created" | "waiting_for_resource" | "preparing" | "pending" | "scheduled" | "manual" => {
+            Status::Pending
+        }
+        "canceled" | "skipped" => Status::Cancelled,
+        _ => Status::Unknown,
+    }
+}
+
+fn is_finished(status: &str) -> bool {
+    matches!(status, "success" | "failed" | "canceled" | "skipped")
+}
+
+/// The list endpoint has no start time, use the creation of the pipeline instead
+fn to_running_info(
+    pipelines: &[PipelineItem],
+    now: chrono::DateTime<chrono::FixedOffset>,
+) -> Option<RunningInfo> {
+    pipelines
+        .iter()
+        .find(|pipeline| pipeline.status == "running")
+        .and_then(|pipeline| {
+            let started_at = chrono::DateTime::parse_from_rfc3339(&pipeline.created_at).ok()?;
+            Some(RunningInfo {
+                url: Some(pipeline.web_url.clone()),
+                ..RunningInfo::new(started_at, now, None)
+            })
+        })
+}
+
+impl PipelineDetails {
+    fn into_build_status(self, running: Option<RunningInfo>) -> BuildStatus {
+        let time_info = match (self.finished_at, self.duration) {
+            (Some(completed_at), Some(duration_secs)) => Some(TimeInfo {
+                completed_at,
+                duration_secs: Some(duration_secs),
+            }),
+            _ => None,
+        };
+        BuildStatus {
+            status: to_status(&self.status),
+            url: self.web_url,
+            time_info,
+            running,
+        }
+    }
+}
+
+pub async fn fetch(
+    server_url: &Option<String>,
+    project: &str,
+    git_ref: &str,
+    token: &Option<String>,
+) -> Result<BuildStatus, Error> {
+    let server_url = server_url.as_deref().unwrap_or(BASE_URL);
+    let project = encode_uri_component(project);
+    let pipelines_url = format!("{server_url}/api/v4/projects/{project}/pipelines");
+    let mut headers = vec![(String::from("Accept"), String::from("application/json"))];
+    if let Some(t) = token {
+        headers.push((String::from("PRIVATE-TOKEN"), t.to_string()));
+    }
+
+    let git_ref = encode_uri_component(git_ref);
+    let list_url = format!("{pipelines_url}?ref={git_ref}&per_page=10");
+    let pipelines = crate::utils::request::<Vec<PipelineItem>>(&list_url, &headers).await?;
+    let running = to_running_info(&pipelines, chrono::Utc::now().fixed_offset());
+    // pipelines are sorted by most recent first, fall back to the
+    // most recent one if none has finished yet
+    let pipeline = pipelines
+        .iter()
+        .find(|pipeline| is_finished(&pipeline.status))
+        .or(pipelines.first())
+        .ok_or(Error::NoBuild)?;
+
+    // only the details have the finish time and duration
+    let details_url = format!("{pipelines_url}/{}", pipeline.id);
+    crate::utils::request::<PipelineDetails>(&details_url, &headers)
+        .await
+        .map(|details| details.into_build_status(running))
+}
+
+/// The pipelines of a branch or tag
+#[derive(Debug, serde::Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GitLabConfig {
+    /// Defaults to `https://gitlab.com`
+    pub server_url: Option<String>,
+    /// Project path (`group/project`) or numeric ID
+    pub project: String,
+    #[serde(rename = "ref")]
+    pub git_ref: String,
+    pub token: Option<String>,
+}
+
+impl Provider for GitLabConfig {
+    fn tag(&self) -> &'static str {
+        "gitlab"
+    }
+
+    fn title(&self) -> String {
+        format!("{}/{}", self.project, self.git_ref)
+    }
+
+    fn web_url(&self) -> String {
+        let server_url = self.server_url.as_deref().unwrap_or(BASE_URL);
+        format!(
+            "{server_url}/{}/-/pipelines?ref={}",
+            self.project,
+            encode_uri_component(&self.git_ref)
+        )
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            time_info: true,
+            running: true,
+        }
+    }
+
+    fn fetch(&self) -> BoxFuture<'_, Result<BuildStatus, Error>> {
+        fetch(&self.server_url, &self.project, &self.git_ref, &self.token).boxed()
+    }
+}
+
+#[cfg(test)]
+mod gitlab_tests {
+    use super::*;
+
+    #[test]
+    fn map_status() {
+        assert_eq!(to_status("success"), Status::Green);
+        assert_eq!(to_status("failed"), Status::Red);
+        assert_eq!(to_status("running"), Status::Running);
+        assert_eq!(to_status("pending"), Status::Pending);
+        assert_eq!(to_status("manual"), Status::Pending);
+        assert_eq!(to_status("canceled"), Status::Cancelled);
+        assert_eq!(to_status("skipped"), Status::Cancelled);
+        assert_eq!(to_status("whatever"), Status::Unknown);
+    }
+
+    #[test]
+    fn encode_ref() {
+        let config = GitLabConfig {
+            server_url: None,
+            project: String::from("my/project"),
+            git_ref: String::from("fix/a&b#1"),
+            token: None,
+        };
+        assert_eq!(
+            config.web_url(),
+            "https://gitlab.com/my/project/-/pipelines?ref=fix%2Fa%26b%231"
+        );
+    }
+
+    #[test]
+    fn decode_pipelines() {
+        let s = String::from(
+            "[{\"id\":47,\"iid\":12,\"project_id\":1,\"status\":\"running\",\"source\":\"push\",\"ref\":\"main\",\"sha\":\"a91957a858320c0e17f3a0eca7cfacbff50ea29a\",\"web_url\":\"https://gitlab.example.com/my/project/pipelines/47\",\"created_at\":\"2025-11-07T08:00:00.000Z\",\"updated_at\":\"2025-11-07T08:01:00.000Z\"},{\"id\":46,\"iid\":11,\"project_id\":1,\"status\":\"failed\",\"source\":\"push\",\"ref\":\"main\",\"sha\":\"b83d6e391c22777fca1ed3012fce84f633d7fed0\",\"web_url\":\"https://gitlab.example.com/my/project/pipelines/46\",\"created_at\":\"2025-11-07T07:00:00.000Z\",\"updated_at\":\"2025-11-07T07:10:00.000Z\"}]",
+        );
+        let pipelines = serde_json::from_str::<Vec<PipelineItem>>(&s).unwrap();
+        assert_eq!(pipelines.len(), 2);
+        let now = chrono::DateTime::parse_from_rfc3339("2025-11-07T08:02:00Z").unwrap();
+        assert_eq!(
+            to_running_info(&pipelines, now),
+            Some(RunningInfo {
+                started_at: Some(String::from("2025-11-07T08:00:00+00:00")),
+                elapsed_secs: Some(120),
+                estimated_secs: None,
+                url: Some(String::from(
+                    "https://gitlab.example.com/my/project/pipelines/47"
+                )),
+            })
+        );
+    }
+
+    #[test]
+    fn convert_details() {
+        let s = String::from(
+            "{\"id\":46,\"iid\":11,\"project_id\":1,\"status\":\"failed\",\"ref\":\"main\",\"web_url\":\"https://gitlab.example.com/my/project/pipelines/46\",\"created_at\":\"2025-11-07T07:00:00.000Z\",\"updated_at\":\"2025-11-07T07:10:00.000Z\",\"started_at\":\"2025-11-07T07:00:05.000Z\",\"finished_at\":\"2025-11-07T07:10:00.000Z\",\"duration\":595,\"queued_duration\":5}",
+        );
+        let details = serde_json::from_str::<PipelineDetails>(&s).unwrap();
+        let expected = BuildStatus {
+            status: Status::Red,
+            url: String::from("https://gitlab.example.com/my/project/pipelines/46"),
+            time_info: Some(TimeInfo {
+                completed_at: String::from("2025-11-07T07:10:00.000Z"),
+                duration_secs: Some(595),
+            }),
+            running: None,
+        };
+        assert_eq!(details.into_build_status(None), expected);
+    }
+}
@@ -0,0 +1,173 @@
+use std::{
+    process::{ExitStatus, Stdio},
+    time::Duration,
+};
+
+use rwatch::{
+    build_status::BuildStatus,
+    config::{BuildConfig, HookConfig},
+    error::Error,
+    utils::{ProcessGroup, shell},
+};
+
+use crate::transitions::Transition;
+
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+
+/// The env variables describing the transition, passed to the hooks
+fn env_vars(
+    build: &BuildConfig,
+    transition: &Transition,
+    result: &Result<BuildStatus, Error>,
+) -> Vec<(&'static str, String)> {
+    let (url, duration, error) = match result {
+        Ok(build_status) => (
+            build_status.url.clone(),
+            build_status
+                .time_info
+                .as_ref()
+                .and_then(|time_info| time_info.duration_secs)
+                .map(|secs| secs.to_string())
+                .unwrap_or_default(),
+            String::new(),
+        ),
+        Err(e) => (String::new(), String::new(), e.to_string()),
+    };
+    vec![
+        ("RWATCH_TITLE", build.get_title()),
+        ("RWATCH_PROVIDER", build.get_tag().to_string()),
+        ("RWATCH_GROUPS", build.get_groups().join(",")),
+        ("RWATCH_OLD_STATUS", transition.previous.name().to_string()),
+        ("RWATCH_NEW_STATUS", transition.current.name().to_string()),
+        ("RWATCH_URL", url),
+        ("RWATCH_DURATION_SECS", duration),
+        ("RWATCH_ERROR", error),
+    ]
+}
+
+/// The output is discarded, it would mess up the table
+async fn run_hook(
+    command: &str,
+    timeout: Duration,
+    env: Vec<(&'static str, String)>,
+) -> Result<ExitStatus, Error> {
+    let mut child = shell(command)
+        .envs(env)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()?;
+    let process_group = ProcessGroup::new(&child);
+    match tokio::time::timeout(timeout, child.wait()).await {
+        Ok(status) => {
+            process_group.disarm();
+            Ok(status?)
+        }
+        Err(_) => {
+            drop(process_group);
+            child.kill().await?;
+            Err(Error::Timeout)
+        }
+    }
+}
+
+/// Run the hooks matching the transition, in the background so that a stuck
+/// hook does not delay the polling
+pub fn run(
+    hooks: &[HookConfig],
+    build: &BuildConfig,
+    transition: &Transition,
+    result: &Result<BuildStatus, Error>,
+) {
+    for hook in hooks
+        .iter()
+        .filter(|hook| hook.applies_to(build, transition.current.name()))
+    {
+        let command = hook.command.clone();
+        let timeout = Duration::from_secs(hook.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS));
+        let env = env_vars(build, transition, result);
+        tokio::spawn(async move { run_hook(&command, timeout, env).await });
+    }
+}
+
+#[cfg(test)]
+mod hooks_tests {
+    use super::*;
+    use crate::transitions::State;
+    use rwatch::{
+        bamboo::BambooConfig,
+        build_status::{Status, TimeInfo},
+        config::ProviderConfig,
+    };
+
+    fn build() -> BuildConfig {
+        BuildConfig {
+            provider: ProviderConfig::Bamboo(BambooConfig {
+                server_url: String::from("http://my.bamboo"),
+                plan: String::from("MY-PLAN"),
+                token: None,
+            }),
+            timeout_secs: None,
+            groups: vec![String::from("g1"), String::from("g2")],
+        }
+    }
+
+    fn transition() -> Transition {
+        Transition {
+            index: 0,
+            previous: State::Status(Status::Green),
+            current: State::Status(Status::Red),
+        }
+    }
+
+    #[test]
+    fn describe_transition() {
+        let result = Ok(BuildStatus {
+            status: Status::Red,
+            url: String::from("http://my.bamboo/browse/MY-PLAN-12"),
+            time_info: Some(TimeInfo {
+                completed_at: String::from("2025-11-07T09:19:46.000+01:00"),
+                duration_secs: Some(1266),
+            }),
+            running: None,
+        });
+        let env = env_vars(&build(), &transition(), &result);
+        let expected = vec![
+            ("RWATCH_TITLE", "MY-PLAN"),
+            ("RWATCH_PROVIDER", "bamboo"),
+            ("RWATCH_GROUPS", "g1,g2"),
+            ("RWATCH_OLD_STATUS", "green"),
+            ("RWATCH_NEW_STATUS", "red"),
+            ("RWATCH_URL", "http://my.bamboo/browse/MY-PLAN-12"),
+            ("RWATCH_DURATION_SECS", "1266"),
+            ("RWATCH_ERROR", ""),
+        ];
+        assert_eq!(
+            env,
+            expected
+                .into_iter()
+                .map(|(k, v)| (k, v.to_string()))
+                .collect::<Vec<_>>()
+        );
+        let env = env_vars(&build(), &transition(), &Err(Error::NotFound));
+        assert_eq!(env[7], ("RWATCH_ERROR", String::from("not found (404)")));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn run_with_timeout() {
+        let env = vec![("RWATCH_TITLE", String::from("MY-PLAN"))];
+        let status = run_hook(
+            "test \"$RWATCH_TITLE\" = MY-PLAN",
+            Duration::from_secs(5),
+            env.clone(),
+        )
+        .await;
+        assert!(status.unwrap().success());
+        let status = run_hook("exit 3", Duration::from_secs(5), env.clone()).await;
+        assert_eq!(status.unwrap().code(), Some(3));
+        let status = run_hook("sleep 10", Duration::from_millis(100), env).await;
+        assert_eq!(status, Err(Error::Timeout));
+    }
+}
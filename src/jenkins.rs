@@ -1,4 +1,24 @@
-use crate::build_status::{BuildStatus, Status};
+use futures::{FutureExt, future::BoxFuture};
+
+use crate::{
+    build_status::{BuildStatus, RunningInfo, Status, TimeInfo},
+    error::Error,
+    provider::{Capabilities, Provider},
+};
+
+const BUILD_TREE: &str = "url,building,timestamp,estimatedDuration,result,duration";
+
+fn to_status(result: Option<&str>, building: bool) -> Status {
+    match result {
+        Some("SUCCESS") => Status::Green,
+        Some("FAILURE") => Status::Red,
+        Some("UNSTABLE") => Status::Unstable,
+        Some("ABORTED") => Status::Cancelled,
+        Some("NOT_BUILT") => Status::Cancelled,
+        None if building => Status::Running,
+        _ => Status::Unknown,
+    }
+}
 
 pub async fn fetch(
     server_url: &String,
@@ -6,9 +26,9 @@ pub async fn fetch(
     branch: &String,
     token: &Option<String>,
     user: &Option<String>,
-) -> Result<BuildStatus, String> {
+) -> Result<BuildStatus, Error> {
     let url = format!(
-        "{server_url}/job/{plan}/job/{branch}/lastCompletedBuild/api/json?tree=url,building,timestamp,estimatedDuration,result,duration&depth=0"
+        "{server_url}/job/{plan}/job/{branch}/api/json?tree=lastBuild[{BUILD_TREE}],lastCompletedBuild[{BUILD_TREE}]"
     );
     let headers = vec![(String::from("Accept"), String::from("application/json"))];
     match user {
@@ -23,28 +43,219 @@ pub async fn fetch(
         }
         None => crate::utils::request::<JenkinsResponse>(&url, &headers).await,
     }
-    .and_then(|response| {
-        let result = response.result.as_str();
-        match result {
-            "SUCCESS" => Ok(BuildStatus {
-                status: Status::Green,
-                url: response.url,
-                time_info: None,
-            }),
-            "FAILURE" => Ok(BuildStatus {
-                status: Status::Red,
-                url: response.url,
-                time_info: None,
-            }),
-            _ => Err(format!("Unhandled result {result}")),
-        }
-    })
+    .and_then(|response| response.into_build_status(chrono::Utc::now().fixed_offset()))
 }
 
 #[derive(Debug, serde::Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
 struct JenkinsResponse {
+    last_build: Option<JenkinsBuild>,
+    last_completed_build: Option<JenkinsBuild>,
+}
+
+#[derive(Debug, serde::Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct JenkinsBuild {
     url: String,
+    #[serde(default)]
+    building: bool,
     duration: u32,
-    result: String,
-    timestamp: u64,
+    /// in millis, -1 when Jenkins has no estimate
+    estimated_duration: i64,
+    result: Option<String>,
+    timestamp: i64,
+}
+
+impl JenkinsBuild {
+    /// `timestamp` is the start of the build, both it and `duration` are in millis
+    fn to_time_info(&self) -> Option<TimeInfo> {
+        if self.building {
+            return None;
+        }
+        let completed_at =
+            chrono::DateTime::from_timestamp_millis(self.timestamp + i64::from(self.duration))?;
+        Some(TimeInfo {
+            completed_at: completed_at.to_rfc3339(),
+            duration_secs: Some(self.duration / 1000),
+        })
+    }
+
+    fn to_running_info(&self, now: chrono::DateTime<chrono::FixedOffset>) -> Option<RunningInfo> {
+        let started_at = chrono::DateTime::from_timestamp_millis(self.timestamp)?;
+        let estimated_secs = u32::try_from(self.estimated_duration / 1000)
+            .ok()
+            .filter(|secs| *secs > 0);
+        Some(RunningInfo {
+            url: Some(self.url.clone()),
+            ..RunningInfo::new(started_at.fixed_offset(), now, estimated_secs)
+        })
+    }
+}
+
+impl JenkinsResponse {
+    fn into_build_status(
+        self,
+        now: chrono::DateTime<chrono::FixedOffset>,
+    ) -> Result<BuildStatus, Error> {
+        let running = self
+            .last_build
+            .as_ref()
+            .filter(|build| build.building)
+            .and_then(|build| build.to_running_info(now));
+        // fall back to the running build if nothing has completed yet
+        match self.last_completed_build.or(self.last_build) {
+            Some(build) => Ok(BuildStatus {
+                status: to_status(build.result.as_deref(), build.building),
+                time_info: build.to_time_info(),
+                url: build.url,
+                running,
+            }),
+            None => Err(Error::NoBuild),
+        }
+    }
+}
+
+/// A branch of a multibranch pipeline
+#[derive(Debug, serde::Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct JenkinsConfig {
+    pub server_url: String,
+    pub plan: String,
+    pub branch: String,
+    pub user: Option<String>,
+    pub token: Option<String>,
+}
+
+impl Provider for JenkinsConfig {
+    fn tag(&self) -> &'static str {
+        "jenkins"
+    }
+
+    fn title(&self) -> String {
+        format!("{}/{}", self.plan, self.branch)
+    }
+
+    fn web_url(&self) -> String {
+        format!("{}/job/{}/job/{}", self.server_url, self.plan, self.branch)
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            time_info: true,
+            running: true,
+        }
+    }
+
+    fn fetch(&self) -> BoxFuture<'_, Result<BuildStatus, Error>> {
+        fetch(
+            &self.server_url,
+            &self.plan,
+            &self.branch,
+            &self.token,
+            &self.user,
+        )
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod jenkins_tests {
+    use super::*;
+
+    #[test]
+    fn map_results() {
+        assert_eq!(to_status(Some("SUCCESS"), false), Status::Green);
+        assert_eq!(to_status(Some("FAILURE"), false), Status::Red);
+        assert_eq!(to_status(Some("UNSTABLE"), false), Status::Unstable);
+        assert_eq!(to_status(Some("ABORTED"), false), Status::Cancelled);
+        assert_eq!(to_status(Some("NOT_BUILT"), false), Status::Cancelled);
+        assert_eq!(to_status(None, true), Status::Running);
+        assert_eq!(to_status(Some("WHATEVER"), false), Status::Unknown);
+    }
+
+    #[test]
+    fn decode_response() {
+        let s = String::from(
+            "{\"_class\":\"org.jenkinsci.plugins.workflow.multibranch.BranchJob\",\"lastBuild\":{\"_class\":\"org.jenkinsci.plugins.workflow.job.WorkflowRun\",\"building\":true,\"duration\":0,\"estimatedDuration\":720000,\"result\":null,\"timestamp\":1762502500000,\"url\":\"https://my.jenkins/job/my-plan/job/main/43/\"},\"lastCompletedBuild\":{\"_class\":\"org.jenkinsci.plugins.workflow.job.WorkflowRun\",\"building\":false,\"duration\":754123,\"estimatedDuration\":700000,\"result\":\"UNSTABLE\",\"timestamp\":1762502320000,\"url\":\"https://my.jenkins/job/my-plan/job/main/42/\"}}",
+        );
+        let v = serde_json::from_str::<JenkinsResponse>(&s).unwrap();
+        let expected = JenkinsResponse {
+            last_build: Some(JenkinsBuild {
+                url: String::from("https://my.jenkins/job/my-plan/job/main/43/"),
+                building: true,
+                duration: 0,
+                estimated_duration: 720000,
+                result: None,
+                timestamp: 1762502500000,
+            }),
+            last_completed_build: Some(JenkinsBuild {
+                url: String::from("https://my.jenkins/job/my-plan/job/main/42/"),
+                building: false,
+                duration: 754123,
+                estimated_duration: 700000,
+                result: Some(String::from("UNSTABLE")),
+                timestamp: 1762502320000,
+            }),
+        };
+        assert_eq!(v, expected);
+    }
+
+    #[test]
+    fn convert_running() {
+        let response = JenkinsResponse {
+            last_build: Some(JenkinsBuild {
+                url: String::from("https://my.jenkins/job/my-plan/job/main/43/"),
+                building: true,
+                duration: 0,
+                estimated_duration: 720000,
+                result: None,
+                timestamp: 1762502500000,
+            }),
+            last_completed_build: Some(JenkinsBuild {
+                url: String::from("https://my.jenkins/job/my-plan/job/main/42/"),
+                building: false,
+                duration: 754123,
+                estimated_duration: 700000,
+                result: Some(String::from("FAILURE")),
+                timestamp: 1762502320000,
+            }),
+        };
+        let now = chrono::DateTime::parse_from_rfc3339("2025-11-07T08:04:40+00:00").unwrap();
+        let expected = BuildStatus {
+            status: Status::Red,
+            url: String::from("https://my.jenkins/job/my-plan/job/main/42/"),
+            time_info: Some(TimeInfo {
+                completed_at: String::from("2025-11-07T08:11:14.123+00:00"),
+                duration_secs: Some(754),
+            }),
+            running: Some(RunningInfo {
+                started_at: Some(String::from("2025-11-07T08:01:40+00:00")),
+                elapsed_secs: Some(180),
+                estimated_secs: Some(720),
+                url: Some(String::from("https://my.jenkins/job/my-plan/job/main/43/")),
+            }),
+        };
+        assert_eq!(response.into_build_status(now), Ok(expected));
+    }
+
+    #[test]
+    fn convert_time_info() {
+        let build = |building: bool, duration: u32| JenkinsBuild {
+            url: String::from("https://my.jenkins/job/my-plan/job/main/42/"),
+            building,
+            duration,
+            estimated_duration: -1,
+            result: None,
+            timestamp: 1762502400000,
+        };
+        assert_eq!(
+            build(false, 90500).to_time_info(),
+            Some(TimeInfo {
+                completed_at: String::from("2025-11-07T08:01:30.500+00:00"),
+                duration_secs: Some(90),
+            })
+        );
+        // duration is 0 until the build completes
+        assert_eq!(build(true, 0).to_time_info(), None);
+    }
 }
@@ -0,0 +1,33 @@
+//! Fetch the status of builds from several CI servers.
+//!
+//! The `rwatch` binary is built on top of this crate, which other tools can
+//! embed to reuse the fetching and rendering :
+//!
+//! ```no_run
+//! # async fn run(content: &String) -> Result<(), rwatch::error::Error> {
+//! let config = rwatch::config::load_config(content, rwatch::config::env_replacer)?;
+//! rwatch::utils::init_http(&config.http)?;
+//! for (build, result) in config.builds.iter().zip(config.fetch_all().await) {
+//!     println!("{} : {:?}", build.get_title(), result.map(|s| s.status));
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! New providers implement [`provider::Provider`].
+
+pub mod bamboo;
+pub mod build_status;
+pub mod cctray;
+pub mod circle_ci;
+pub mod config;
+pub mod custom;
+pub mod error;
+pub mod exec;
+pub mod github_actions;
+pub mod gitlab;
+pub mod jenkins;
+pub mod provider;
+pub mod rendering;
+pub mod travis;
+pub mod utils;
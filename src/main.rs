@@ -1,47 +1,135 @@
-mod bamboo;
-mod build_status;
-mod circle_ci;
-mod config;
-mod jenkins;
-mod rendering;
-mod travis;
-mod utils;
+mod cli;
+mod exit_codes;
+mod hooks;
+mod notify;
+mod serve;
+mod transitions;
+mod tui;
+mod watch;
 
-use std;
+use std::process::ExitCode;
 
-use crate::{
-    build_status::BuildStatus,
-    config::{BuildConfig, env_replacer, load_config},
-    rendering::render_rows,
+use clap::Parser;
+
+use rwatch::{
+    config::{env_replacer, load_config, parse_time_zone, validate_config},
+    error::Error,
+    rendering::{RenderOptions, render_list, render_results},
+    utils,
 };
 
-static CONFIG_FILE: &str = ".bwatch.json";
+use crate::{
+    cli::{Cli, Command},
+    serve::serve,
+    transitions::Transitions,
+    tui::tui,
+    watch::watch,
+};
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let mut config_file = std::env::home_dir().unwrap();
-    config_file.push(CONFIG_FILE);
-    let content = std::fs::read_to_string(config_file).unwrap();
-    let config = load_config(&content, env_replacer).unwrap();
-    let futures = config
-        .builds
-        .into_iter()
-        .map(async |x| match x.fetch().await {
-            Ok(r) => Ok((r, x)),
-            Err(e) => Err((e, x)),
-        });
-    let joined = futures::future::join_all(futures).await;
-    let mut rows: Vec<(&BuildConfig, &BuildStatus)> = Vec::new();
-    for r in joined.iter() {
-        match r {
-            Ok((status, config)) => {
-                rows.push((config, status));
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(&cli).await {
+        Ok(code) => ExitCode::from(code),
+        Err(e) => {
+            if !cli.quiet {
+                eprintln!("💣 {e}");
+            }
+            ExitCode::from(exit_codes::ERROR)
+        }
+    }
+}
+
+async fn run(cli: &Cli) -> Result<u8, Error> {
+    let config_file = cli.config_file().map_err(Error::Config)?;
+    let content = std::fs::read_to_string(&config_file)
+        .map_err(|e| Error::Config(format!("cannot read {} : {e}", config_file.display())))?;
+
+    if cli.command() == Command::Validate {
+        let problems = validate_config(&content, env_replacer);
+        if problems.is_empty() {
+            if !cli.quiet {
+                println!("✅ {} is valid", config_file.display());
+            }
+            return Ok(exit_codes::SUCCESS);
+        }
+        if !cli.quiet {
+            for problem in problems.iter() {
+                println!("❌ {problem}");
             }
-            Err((e, config)) => {
-                println!("💣 {} {:?}", config.get_title(), e);
+        }
+        return Err(Error::Config(format!(
+            "{} problem(s) found in {}",
+            problems.len(),
+            config_file.display()
+        )));
+    }
+
+    let mut config = load_config(&content, env_replacer)?;
+    utils::init_http(&config.http)?;
+    if !cli.groups.is_empty() {
+        config.retain_groups(&cli.groups);
+    }
+    let groups = if !cli.by_group {
+        None
+    } else if cli.groups.is_empty() {
+        Some(config.group_names())
+    } else {
+        Some(cli.groups.clone())
+    };
+    let time_zone = cli
+        .time_zone
+        .as_ref()
+        .or(config.display.time_zone.as_ref())
+        .map(|time_zone| parse_time_zone(time_zone))
+        .transpose()?;
+    let options = RenderOptions {
+        format: cli.output,
+        groups,
+        time_zone,
+        relative_times: cli
+            .relative_times()
+            .unwrap_or(config.display.relative_times),
+        stale_after: config
+            .display
+            .stale_after_hours
+            .and_then(|hours| chrono::Duration::try_hours(i64::try_from(hours).ok()?)),
+    };
+    if cli.notify
+        && matches!(
+            cli.command(),
+            Command::Watch | Command::Tui | Command::Serve
+        )
+    {
+        notify::check().await?;
+    }
+    let transitions = Transitions::new(cli.notify);
+    match cli.command() {
+        Command::Status => {
+            let results = config.fetch_all().await;
+            if !cli.quiet {
+                render_results(&config.builds, &results, &options);
+            }
+            Ok(exit_codes::from_results(&results))
+        }
+        Command::Watch => {
+            watch(&config, &options, transitions, cli.quiet).await;
+            Ok(exit_codes::SUCCESS)
+        }
+        Command::Tui => {
+            tui(config, options, transitions).await?;
+            Ok(exit_codes::SUCCESS)
+        }
+        Command::Serve => {
+            serve(config, options, transitions, &cli.listen, cli.quiet).await?;
+            Ok(exit_codes::SUCCESS)
+        }
+        Command::List => {
+            if !cli.quiet {
+                render_list(&config.builds);
             }
+            Ok(exit_codes::SUCCESS)
         }
+        Command::Validate => unreachable!(),
     }
-    render_rows(rows);
-    Ok(())
 }
@@ -0,0 +1,146 @@
+use rwatch::{
+    build_status::{BuildStatus, Status},
+    error::Error,
+    rendering::escape,
+};
+
+use crate::transitions::State;
+
+#[derive(Debug, PartialEq)]
+pub struct Notification {
+    pub summary: String,
+    /// May contain markup, which servers without support strip
+    pub body: String,
+    pub critical: bool,
+}
+
+fn to_notification(
+    title: &str,
+    previous: State,
+    result: &Result<BuildStatus, Error>,
+) -> Option<Notification> {
+    let build_status = match result {
+        Ok(build_status) => build_status,
+        Err(e) => {
+            return Some(Notification {
+                summary: format!("💣 {title} cannot be fetched"),
+                body: escape(&e.to_string()),
+                critical: true,
+            });
+        }
+    };
+    let summary = match (previous, build_status.status) {
+        (State::Failing, _) => format!("{title} can be fetched again"),
+        (_, Status::Green) => format!("✅ {title} is fixed"),
+        (_, Status::Red) => format!("❌ {title} is broken"),
+        (_, Status::Unstable) => format!("🟡 {title} is unstable"),
+        _ => return None,
+    };
+    let url = escape(&build_status.url);
+    Some(Notification {
+        summary,
+        body: format!("<a href=\"{url}\">{url}</a>"),
+        critical: build_status.status == Status::Red,
+    })
+}
+
+/// Notify a transition, without waiting for the notification to be sent
+pub fn notify(title: &str, previous: State, result: &Result<BuildStatus, Error>) {
+    if let Some(notification) = to_notification(title, previous, result) {
+        tokio::spawn(async move { send(&notification).await });
+    }
+}
+
+#[cfg(target_os = "linux")]
+static CONNECTION: tokio::sync::OnceCell<zbus::Connection> = tokio::sync::OnceCell::const_new();
+
+#[cfg(target_os = "linux")]
+async fn connection() -> Result<&'static zbus::Connection, Error> {
+    CONNECTION
+        .get_or_try_init(zbus::Connection::session)
+        .await
+        .map_err(|e| Error::Io(format!("cannot connect to the session bus : {e}")))
+}
+
+/// Fail early when notifications cannot be sent, the later failures are ignored
+#[cfg(target_os = "linux")]
+pub async fn check() -> Result<(), Error> {
+    connection().await.map(|_| ())
+}
+
+/// Through the freedesktop notifications D-Bus interface
+#[cfg(target_os = "linux")]
+async fn send(notification: &Notification) -> Result<(), Error> {
+    use std::collections::HashMap;
+    use zbus::zvariant::Value;
+
+    let urgency: u8 = if notification.critical { 2 } else { 1 };
+    let hints = HashMap::from([("urgency", Value::U8(urgency))]);
+    let actions: Vec<&str> = Vec::new();
+    connection()
+        .await?
+        .call_method(
+            Some("org.freedesktop.Notifications"),
+            "/org/freedesktop/Notifications",
+            Some("org.freedesktop.Notifications"),
+            "Notify",
+            &(
+                "rwatch",
+                0u32,
+                "",
+                notification.summary.as_str(),
+                notification.body.as_str(),
+                actions,
+                hints,
+                -1i32,
+            ),
+        )
+        .await
+        .map_err(|e| Error::Io(format!("cannot send notification : {e}")))?;
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub async fn check() -> Result<(), Error> {
+    Err(Error::Io(String::from(
+        "desktop notifications are only supported on Linux",
+    )))
+}
+
+#[cfg(not(target_os = "linux"))]
+async fn send(_notification: &Notification) -> Result<(), Error> {
+    check().await
+}
+
+#[cfg(test)]
+mod notify_tests {
+    use super::*;
+
+    fn result(status: Status) -> Result<BuildStatus, Error> {
+        Ok(BuildStatus {
+            status,
+            url: String::from("http://my.bamboo/browse/MY-PLAN-12?a=1&b=2"),
+            time_info: None,
+            running: None,
+        })
+    }
+
+    #[test]
+    fn notification() {
+        let n = to_notification(
+            "MY-PLAN",
+            State::Status(Status::Green),
+            &result(Status::Red),
+        );
+        assert_eq!(
+            n,
+            Some(Notification {
+                summary: String::from("❌ MY-PLAN is broken"),
+                body: String::from(
+                    "<a href=\"http://my.bamboo/browse/MY-PLAN-12?a=1&amp;b=2\">http://my.bamboo/browse/MY-PLAN-12?a=1&amp;b=2</a>"
+                ),
+                critical: true,
+            })
+        );
+    }
+}
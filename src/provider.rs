@@ -0,0 +1,41 @@
+use futures::future::BoxFuture;
+
+use crate::{build_status::BuildStatus, error::Error};
+
+/// What a provider reports besides the status of the last build
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Capabilities {
+    /// Completion time and duration of the last build
+    pub time_info: bool,
+    /// Progress of the build in progress, if any
+    pub running: bool,
+}
+
+/// A CI server, and the build to watch on it
+pub trait Provider: Send + Sync {
+    /// Identifies the provider, it is the `tag` of the build in the config
+    fn tag(&self) -> &'static str;
+
+    /// Names the build in the output, unique within a config
+    fn title(&self) -> String;
+
+    /// Page of the build on the CI, for when no build status is available
+    fn web_url(&self) -> String;
+
+    fn capabilities(&self) -> Capabilities;
+
+    /// Problems of the config of the build, checked when loading it so that
+    /// they are not reported on every fetch
+    fn validate(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Applies when the build has no `timeoutSecs`, HTTP requests have
+    /// their own timeout
+    fn default_timeout_secs(&self) -> Option<u64> {
+        None
+    }
+
+    /// The status of the last build, through the shared HTTP client
+    fn fetch(&self) -> BoxFuture<'_, Result<BuildStatus, Error>>;
+}
@@ -2,80 +2,581 @@ use pad::PadStr;
 use terminal_hyperlink::Hyperlink;
 
 use crate::{
-    build_status::{BuildStatus, Status},
+    build_status::{BuildStatus, RunningInfo, Status},
+    cctray,
     config::BuildConfig,
+    error::Error,
 };
 
+/// A build as displayed, shared by the table and the interactive UI
 pub struct RowData {
-    status: char,
+    pub status: char,
+    pub progress: Option<String>,
+    pub title: String,
+    pub url: String,
+    pub completed_at: String,
+    /// The last result is older than `RenderOptions::stale_after`
+    pub stale: bool,
+    pub duration: String,
+    pub groups: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Human readable table
+    Table,
+    /// JSON document (JSON Lines in watch mode)
+    Json,
+    /// CCTray XML feed, for tray apps and radiators
+    #[value(name = "cctray")]
+    CCTray,
+}
+
+pub struct RenderOptions {
+    pub format: OutputFormat,
+    /// Print a heading per group, for these groups
+    pub groups: Option<Vec<String>>,
+    /// Time zone of the displayed times, local time when `None`
+    pub time_zone: Option<chrono_tz::Tz>,
+    /// Show "12 min ago" rather than the completion date
+    pub relative_times: bool,
+    /// Highlight the builds completed longer ago than this
+    pub stale_after: Option<chrono::Duration>,
+}
+
+/// A build as output in JSON, along with its status or error
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonRow<'a> {
     title: String,
-    url: String,
-    completed_at: String,
-    duration: String,
+    provider: &'static str,
+    groups: &'a [String],
+    #[serde(flatten)]
+    build_status: Option<&'a BuildStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error_kind: Option<&'static str>,
+}
+
+impl<'a> JsonRow<'a> {
+    fn new(config: &'a BuildConfig, result: &'a Result<BuildStatus, Error>) -> Self {
+        let (build_status, error, error_kind) = match result {
+            Ok(status) => (Some(status), None, None),
+            Err(e) => (None, Some(e.to_string()), Some(e.kind())),
+        };
+        JsonRow {
+            title: config.get_title(),
+            provider: config.get_tag(),
+            groups: config.get_groups(),
+            build_status,
+            error,
+            error_kind,
+        }
+    }
 }
 
 const STATUS_GREEN: char = '✅';
 const STATUS_RED: char = '❌';
+const STATUS_RUNNING: char = '⏳';
+const STATUS_PENDING: char = '💤';
+const STATUS_CANCELLED: char = '🚫';
+const STATUS_UNSTABLE: char = '🟡';
+const STATUS_UNKNOWN: char = '❔';
+
+/// Heading of the builds that belong to none of the groups
+pub const UNGROUPED: &str = "(no group)";
+
+const DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+const STALE_STYLE: &str = "\x1b[33m";
+const RESET_STYLE: &str = "\x1b[0m";
+
+/// For text embedded in HTML or in the markup of notifications
+pub fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
 
 fn status_to_string(status: &Status) -> char {
     match status {
         Status::Green => STATUS_GREEN,
         Status::Red => STATUS_RED,
+        Status::Running => STATUS_RUNNING,
+        Status::Pending => STATUS_PENDING,
+        Status::Cancelled => STATUS_CANCELLED,
+        Status::Unstable => STATUS_UNSTABLE,
+        Status::Unknown => STATUS_UNKNOWN,
     }
 }
 
-pub fn render_rows(rows: Vec<(&BuildConfig, &BuildStatus)>) -> () {
-    let mut res: Vec<RowData> = Vec::new();
-    let mut max_title = 0;
-    let mut max_url = 0;
-    let mut max_completed_at = 0;
-    let mut max_duration = 0;
+pub fn render_results(
+    builds: &[BuildConfig],
+    results: &[Result<BuildStatus, Error>],
+    options: &RenderOptions,
+) {
+    match options.format {
+        OutputFormat::Table => render_table(builds, results, options),
+        OutputFormat::Json => render_json(builds, results),
+        OutputFormat::CCTray => println!("{}", cctray::to_feed(builds, results)),
+    }
+}
+
+fn render_table(
+    builds: &[BuildConfig],
+    results: &[Result<BuildStatus, Error>],
+    options: &RenderOptions,
+) {
+    render_rows(builds.iter().zip(results.iter()).collect(), options);
+}
+
+pub fn json_rows<'a>(
+    builds: &'a [BuildConfig],
+    results: &'a [Result<BuildStatus, Error>],
+) -> Vec<JsonRow<'a>> {
+    builds
+        .iter()
+        .zip(results.iter())
+        .map(|(config, result)| JsonRow::new(config, result))
+        .collect()
+}
+
+/// Print all the results as a single JSON array
+pub fn render_json(builds: &[BuildConfig], results: &[Result<BuildStatus, Error>]) {
+    let rows = json_rows(builds, results);
+    println!("{}", serde_json::to_string_pretty(&rows).unwrap());
+}
 
-    for (config, status) in rows.into_iter() {
-        max_title = std::cmp::max(max_title, config.get_title().len());
-        max_url = std::cmp::max(max_url, status.url.len());
+/// Print the results as JSON Lines, one build per line
+pub fn render_json_lines(builds: &[BuildConfig], results: &[Result<BuildStatus, Error>]) {
+    for row in json_rows(builds, results) {
+        println!("{}", serde_json::to_string(&row).unwrap());
+    }
+}
+
+/// Print the configured builds, without fetching them
+pub fn render_list(builds: &[BuildConfig]) {
+    let max_title = builds
+        .iter()
+        .map(|b| b.get_title().len())
+        .max()
+        .unwrap_or(0);
+    let max_tag = builds.iter().map(|b| b.get_tag().len()).max().unwrap_or(0);
+    for build in builds.iter() {
+        let title = build.get_title().pad_to_width(max_title);
+        let tag = build.get_tag().pad_to_width(max_tag);
+        let groups = build.get_groups().join(", ");
+        println!("{title} | {tag} | {groups}");
+    }
+}
 
-        let (completed_at, duration) = status
+fn short_duration(secs: u32) -> String {
+    if secs < 60 {
+        format!("{secs}s")
+    } else if secs < 3600 {
+        format!("{}m", secs / 60)
+    } else {
+        format!("{}h{:02}", secs / 3600, (secs % 3600) / 60)
+    }
+}
+
+/// Elapsed time of a running build, with the estimated duration if known : "3m/12m".
+/// Empty when the start of the build is unknown.
+fn progress_to_string(running: &RunningInfo) -> String {
+    let Some(elapsed_secs) = running.elapsed_secs else {
+        return String::new();
+    };
+    let elapsed = short_duration(elapsed_secs);
+    match running.estimated_secs {
+        Some(estimated_secs) => format!("{elapsed}/{}", short_duration(estimated_secs)),
+        None => elapsed,
+    }
+}
+
+/// "12 min ago", from the age of a build in seconds
+fn time_ago(secs: i64) -> String {
+    if secs < 60 {
+        String::from("just now")
+    } else if secs < 3600 {
+        format!("{} min ago", secs / 60)
+    } else if secs < 86400 {
+        format!("{} h ago", secs / 3600)
+    } else if secs < 2 * 86400 {
+        String::from("1 day ago")
+    } else {
+        format!("{} days ago", secs / 86400)
+    }
+}
+
+/// Completion time as displayed, and whether the build is stale. Times that
+/// cannot be parsed are displayed as is.
+fn completed_at_to_string(
+    completed_at: &str,
+    options: &RenderOptions,
+    now: chrono::DateTime<chrono::Utc>,
+) -> (String, bool) {
+    let Ok(date) = chrono::DateTime::parse_from_rfc3339(completed_at) else {
+        return (completed_at.to_string(), false);
+    };
+    let age = now.signed_duration_since(date);
+    let stale = options
+        .stale_after
+        .is_some_and(|stale_after| age > stale_after);
+    let s = if options.relative_times {
+        time_ago(age.num_seconds())
+    } else {
+        match options.time_zone {
+            Some(time_zone) => date
+                .with_timezone(&time_zone)
+                .format(DATE_FORMAT)
+                .to_string(),
+            None => date
+                .with_timezone(&chrono::Local)
+                .format(DATE_FORMAT)
+                .to_string(),
+        }
+    };
+    (s, stale)
+}
+
+impl RowData {
+    pub fn new(
+        config: &BuildConfig,
+        status: &BuildStatus,
+        options: &RenderOptions,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> RowData {
+        let (completed_at, stale, duration) = status
             .time_info
             .as_ref()
             .map(|time_info| {
-                let parsed_date = chrono::DateTime::parse_from_rfc3339(&time_info.completed_at)
-                    .map(|parsed_date| parsed_date.format("%Y-%m-%d %H:%M:%S").to_string())
-                    .ok()
-                    .unwrap_or(time_info.completed_at.to_string());
-                let secs: u64 = u64::try_from(time_info.duration_secs).unwrap();
-                let d = std::time::Duration::from_secs(secs);
-                let pretty = pretty_duration::pretty_duration(&d, None);
-                (parsed_date, pretty)
+                let (completed_at, stale) =
+                    completed_at_to_string(&time_info.completed_at, options, now);
+                let pretty = time_info
+                    .duration_secs
+                    .map(|secs| {
+                        let d = std::time::Duration::from_secs(u64::from(secs));
+                        pretty_duration::pretty_duration(&d, None)
+                    })
+                    .unwrap_or_default();
+                (completed_at, stale, pretty)
             })
-            .unwrap_or((String::new(), String::new()));
-
-        max_completed_at = std::cmp::max(max_completed_at, completed_at.len());
-        max_duration = std::cmp::max(max_duration, duration.len());
-
-        res.push(RowData {
+            .unwrap_or((String::new(), false, String::new()));
+        RowData {
             status: status_to_string(&status.status),
+            progress: status.running.as_ref().map(progress_to_string),
             title: config.get_title(),
             url: status.url.to_string(),
             completed_at,
+            stale,
             duration,
-        });
+            groups: config.get_groups().to_vec(),
+        }
+    }
+}
+
+/// Print the rows as a table. When `groups` is provided, a heading is printed
+/// for each group, followed by the builds of this group.
+pub fn render_rows(
+    rows: Vec<(&BuildConfig, &Result<BuildStatus, Error>)>,
+    options: &RenderOptions,
+) {
+    for line in table_lines(rows, options, chrono::Utc::now()) {
+        println!("{line}");
+    }
+}
+
+/// The builds that cannot be fetched come first, as "💣 title | error"
+fn table_lines(
+    rows: Vec<(&BuildConfig, &Result<BuildStatus, Error>)>,
+    options: &RenderOptions,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut errors: Vec<(&[String], String)> = Vec::new();
+    let mut res: Vec<RowData> = Vec::new();
+    let mut max_title = 0;
+    let mut max_url = 0;
+    let mut max_completed_at = 0;
+    let mut max_duration = 0;
+    let mut max_progress: Option<usize> = None;
+
+    for (config, result) in rows.into_iter() {
+        let status = match result {
+            Ok(status) => status,
+            Err(e) => {
+                errors.push((
+                    config.get_groups(),
+                    format!("💣 {} | {e}", config.get_title()),
+                ));
+                continue;
+            }
+        };
+        let row = RowData::new(config, status, options, now);
+        max_title = std::cmp::max(max_title, row.title.len());
+        max_url = std::cmp::max(max_url, row.url.len());
+        max_completed_at = std::cmp::max(max_completed_at, row.completed_at.len());
+        max_duration = std::cmp::max(max_duration, row.duration.len());
+        if let Some(progress) = &row.progress {
+            max_progress = Some(std::cmp::max(max_progress.unwrap_or(0), progress.len()));
+        }
+        res.push(row);
     }
 
     res.sort_by(|a, b| a.title.cmp(&b.title));
-    for row in res.into_iter() {
+    let format_row = |row: &RowData| {
         let status = row.status;
+        // "❌ → ⏳ 3m/12m", only when a build is running
+        let running = match (max_progress, &row.progress) {
+            (Some(max_progress), Some(progress)) => format!(
+                " → {STATUS_RUNNING} {}",
+                progress.pad_to_width(max_progress)
+            ),
+            // " → ⏳ " takes 6 columns, the emoji being double width
+            (Some(max_progress), None) => " ".repeat(6 + max_progress),
+            (None, _) => String::new(),
+        };
         let title = &row.title.pad_to_width(max_title);
         let clickable_title = title.hyperlink(&row.url);
-        let completed_at = &row
+        let mut completed_at = row
             .completed_at
             .pad_to_width_with_alignment(max_completed_at, pad::Alignment::Right);
+        if row.stale {
+            completed_at = format!("{STALE_STYLE}{completed_at}{RESET_STYLE}");
+        }
         let duration = &row
             .duration
             .pad_to_width_with_alignment(max_duration, pad::Alignment::Right);
-        let row = format!("{status} {clickable_title} | {completed_at} | {duration}");
-        println!("{row}");
+        format!("{status}{running} {clickable_title} | {completed_at} | {duration}")
+    };
+
+    match options.groups.as_deref() {
+        None => {
+            lines.extend(errors.into_iter().map(|(_, error)| error));
+            lines.extend(res.iter().map(format_row));
+        }
+        Some(groups) => {
+            let mut group_lines = |heading: &str, errors: Vec<&String>, rows: Vec<&RowData>| {
+                if errors.is_empty() && rows.is_empty() {
+                    return;
+                }
+                if !lines.is_empty() {
+                    lines.push(String::new());
+                }
+                lines.push(heading.to_string());
+                lines.extend(errors.into_iter().map(|error| format!("  {error}")));
+                lines.extend(rows.into_iter().map(|row| format!("  {}", format_row(row))));
+            };
+            for group in groups {
+                group_lines(
+                    group,
+                    errors
+                        .iter()
+                        .filter(|(g, _)| g.contains(group))
+                        .map(|(_, error)| error)
+                        .collect(),
+                    res.iter().filter(|r| r.groups.contains(group)).collect(),
+                );
+            }
+            group_lines(
+                UNGROUPED,
+                errors
+                    .iter()
+                    .filter(|(g, _)| !g.iter().any(|g| groups.contains(g)))
+                    .map(|(_, error)| error)
+                    .collect(),
+                res.iter()
+                    .filter(|r| !r.groups.iter().any(|g| groups.contains(g)))
+                    .collect(),
+            );
+        }
     }
+    lines
+}
 
-    ()
+#[cfg(test)]
+mod rendering_tests {
+    use super::*;
+    use crate::{
+        bamboo::BambooConfig, build_status::TimeInfo, config::ProviderConfig,
+        jenkins::JenkinsConfig,
+    };
+
+    #[test]
+    fn escape_markup() {
+        assert_eq!(
+            escape("<a href=\"?a=1&b=2\">"),
+            "&lt;a href=&quot;?a=1&amp;b=2&quot;&gt;"
+        );
+    }
+
+    #[test]
+    fn progress() {
+        let running = RunningInfo {
+            started_at: Some(String::from("2025-11-07T08:01:40+00:00")),
+            elapsed_secs: Some(200),
+            estimated_secs: Some(720),
+            url: None,
+        };
+        assert_eq!(progress_to_string(&running), "3m/12m");
+        let running = RunningInfo {
+            started_at: Some(String::from("2025-11-07T08:01:40+00:00")),
+            elapsed_secs: Some(3900),
+            estimated_secs: None,
+            url: None,
+        };
+        assert_eq!(progress_to_string(&running), "1h05");
+        let running = RunningInfo {
+            started_at: None,
+            elapsed_secs: None,
+            estimated_secs: None,
+            url: None,
+        };
+        assert_eq!(progress_to_string(&running), "");
+        assert_eq!(short_duration(42), "42s");
+    }
+
+    fn options(time_zone: Option<chrono_tz::Tz>, relative_times: bool) -> RenderOptions {
+        RenderOptions {
+            format: OutputFormat::Table,
+            groups: None,
+            time_zone,
+            relative_times,
+            stale_after: Some(chrono::Duration::hours(24)),
+        }
+    }
+
+    #[test]
+    fn completed_at() {
+        let now = chrono::DateTime::parse_from_rfc3339("2025-11-07T10:00:00Z")
+            .unwrap()
+            .to_utc();
+        let paris = options(Some(chrono_tz::Europe::Paris), false);
+        // Bamboo reports in the server time zone, Travis in UTC
+        assert_eq!(
+            completed_at_to_string("2025-11-07T09:19:46.000+01:00", &paris, now),
+            (String::from("2025-11-07 09:19:46"), false)
+        );
+        assert_eq!(
+            completed_at_to_string("2025-11-07T08:19:46Z", &paris, now),
+            (String::from("2025-11-07 09:19:46"), false)
+        );
+        let relative = options(None, true);
+        assert_eq!(
+            completed_at_to_string("2025-11-07T09:48:00Z", &relative, now),
+            (String::from("12 min ago"), false)
+        );
+        assert_eq!(
+            completed_at_to_string("2025-11-04T09:00:00Z", &relative, now),
+            (String::from("3 days ago"), true)
+        );
+        assert_eq!(
+            completed_at_to_string("yesterday", &relative, now),
+            (String::from("yesterday"), false)
+        );
+        assert_eq!(time_ago(30), "just now");
+        assert_eq!(time_ago(7300), "2 h ago");
+        assert_eq!(time_ago(90000), "1 day ago");
+    }
+
+    #[test]
+    fn json_rows() {
+        let builds = vec![
+            BuildConfig {
+                provider: ProviderConfig::Bamboo(BambooConfig {
+                    server_url: String::from("http://my.bamboo"),
+                    plan: String::from("MY-PLAN"),
+                    token: None,
+                }),
+                timeout_secs: None,
+                groups: vec![String::from("g1")],
+            },
+            BuildConfig {
+                provider: ProviderConfig::Jenkins(JenkinsConfig {
+                    server_url: String::from("https://my.jenkins"),
+                    plan: String::from("my-plan"),
+                    branch: String::from("main"),
+                    user: None,
+                    token: None,
+                }),
+                timeout_secs: None,
+                groups: vec![],
+            },
+        ];
+        let results = vec![
+            Ok(BuildStatus {
+                status: Status::Red,
+                url: String::from("http://my.bamboo/browse/MY-PLAN-12"),
+                time_info: Some(TimeInfo {
+                    completed_at: String::from("2025-11-07T09:19:46.000+01:00"),
+                    duration_secs: Some(1266),
+                }),
+                running: None,
+            }),
+            Err(Error::NotFound),
+        ];
+        let json = serde_json::to_value(super::json_rows(&builds, &results)).unwrap();
+        let expected = serde_json::json!([
+            {
+                "title": "MY-PLAN",
+                "provider": "bamboo",
+                "groups": ["g1"],
+                "status": "red",
+                "url": "http://my.bamboo/browse/MY-PLAN-12",
+                "timeInfo": {
+                    "completedAt": "2025-11-07T09:19:46.000+01:00",
+                    "durationSecs": 1266
+                },
+                "running": null
+            },
+            {
+                "title": "my-plan/main",
+                "provider": "jenkins",
+                "groups": [],
+                "error": "not found (404)",
+                "errorKind": "notFound"
+            }
+        ]);
+        assert_eq!(json, expected);
+    }
+
+    #[test]
+    fn errors_in_groups() {
+        let builds: Vec<BuildConfig> = serde_json::from_str(
+            "[{\"tag\":\"bamboo\",\"serverUrl\":\"http://my.bamboo\",\"plan\":\"MY-PLAN\",\"groups\":[\"g1\"]},{\"tag\":\"bamboo\",\"serverUrl\":\"http://my.bamboo\",\"plan\":\"OTHER-PLAN\",\"groups\":[\"g2\"]},{\"tag\":\"exec\",\"title\":\"tests\",\"command\":\"make test\"}]",
+        )
+        .unwrap();
+        let results = vec![
+            Ok(BuildStatus {
+                status: Status::Green,
+                url: String::from("http://my.bamboo/browse/MY-PLAN-12"),
+                time_info: None,
+                running: None,
+            }),
+            Err(Error::NotFound),
+            Err(Error::Timeout),
+        ];
+        let mut options = options(None, false);
+        options.groups = Some(vec![String::from("g1"), String::from("g2")]);
+        let lines = table_lines(
+            builds.iter().zip(results.iter()).collect(),
+            &options,
+            chrono::Utc::now(),
+        );
+        assert_eq!(lines.len(), 8);
+        assert_eq!(lines[0], "g1");
+        assert!(lines[1].starts_with("  ✅"));
+        assert_eq!(
+            lines[2..],
+            [
+                "",
+                "g2",
+                "  💣 OTHER-PLAN | not found (404)",
+                "",
+                "(no group)",
+                "  💣 tests | timed out",
+            ]
+        );
+    }
 }
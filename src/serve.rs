@@ -0,0 +1,294 @@
+use std::{
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use axum::{
+    Router,
+    extract::State,
+    http::header,
+    response::{Html, IntoResponse},
+    routing::get,
+};
+
+use rwatch::{
+    build_status::{BuildStatus, Status},
+    cctray,
+    config::{BuildConfig, Config},
+    error::Error,
+    rendering::{JsonRow, RenderOptions, RowData, UNGROUPED, escape, json_rows},
+};
+
+use crate::{
+    transitions::Transitions,
+    watch::{DEFAULT_POLLING_INTERVAL, keep_rate_limited},
+};
+
+/// The page only reads the last results, it can reload often
+const PAGE_REFRESH_SECS: u64 = 10;
+const STYLE: &str = "body{margin:0;padding:1rem;background:#111;color:#eee;font-family:sans-serif}\
+header{display:flex;justify-content:space-between;color:#999}\
+h2{margin:1.5rem 0 .5rem;font-weight:normal;color:#bbb}\
+.tiles{display:grid;grid-template-columns:repeat(auto-fill,minmax(20rem,1fr));gap:1rem}\
+.tile{display:block;padding:1.5rem;border-radius:.5rem;color:#fff;text-decoration:none;background:#555}\
+.title{font-size:1.8rem;font-weight:bold;overflow-wrap:anywhere}\
+.details{margin-top:.5rem;font-size:1.2rem}\
+.green{background:#2e7d32}.red,.error{background:#c62828}.unstable{background:#f9a825;color:#111}\
+.running{background:#1565c0}.pending,.cancelled,.unknown,.loading{background:#555}\
+.stale{color:#ffeb3b}.unstable .stale{color:#6d4c00}";
+
+/// The results of the last poll, shared by the poller and the requests
+#[derive(Default)]
+struct Snapshot {
+    /// Empty until the first poll completes
+    results: Vec<Result<BuildStatus, Error>>,
+    last_refresh: Option<chrono::DateTime<chrono::Local>>,
+}
+
+#[derive(Clone)]
+struct AppState {
+    config: Arc<Config>,
+    options: Arc<RenderOptions>,
+    snapshot: Arc<RwLock<Snapshot>>,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiStatus<'a> {
+    last_refresh: Option<String>,
+    builds: Vec<JsonRow<'a>>,
+}
+
+fn status_class(status: Status) -> &'static str {
+    match status {
+        Status::Green => "green",
+        Status::Red => "red",
+        Status::Running => "running",
+        Status::Pending => "pending",
+        Status::Cancelled => "cancelled",
+        Status::Unstable => "unstable",
+        Status::Unknown => "unknown",
+    }
+}
+
+fn tile(
+    build: &BuildConfig,
+    result: Option<&Result<BuildStatus, Error>>,
+    options: &RenderOptions,
+    now: chrono::DateTime<chrono::Utc>,
+) -> String {
+    let (class, url, details) = match result {
+        None => ("loading", build.provider().web_url(), String::from("…")),
+        Some(Err(e)) => (
+            "error",
+            build.provider().web_url(),
+            format!("💣 {}", escape(&e.to_string())),
+        ),
+        Some(Ok(build_status)) => {
+            let row = RowData::new(build, build_status, options, now);
+            let mut details = row.status.to_string();
+            if let Some(progress) = &row.progress {
+                details.push_str(&format!(" → ⏳ {progress}"));
+            }
+            if !row.completed_at.is_empty() {
+                let class = if row.stale { "stale" } else { "" };
+                details.push_str(&format!(
+                    " <span class=\"{class}\">{}</span>",
+                    escape(&row.completed_at)
+                ));
+            }
+            if !row.duration.is_empty() {
+                details.push_str(&format!(" · {}", escape(&row.duration)));
+            }
+            (status_class(build_status.status), row.url, details)
+        }
+    };
+    format!(
+        "<a class=\"tile {class}\" href=\"{}\"><div class=\"title\">{}</div><div class=\"details\">{details}</div></a>",
+        escape(&url),
+        escape(&build.get_title())
+    )
+}
+
+/// Self-contained, so that it works offline : no script, no external asset
+fn page(
+    config: &Config,
+    options: &RenderOptions,
+    snapshot: &Snapshot,
+    now: chrono::DateTime<chrono::Utc>,
+) -> String {
+    let tiles = |builds: Vec<usize>| {
+        let tiles: String = builds
+            .into_iter()
+            .map(|i| tile(&config.builds[i], snapshot.results.get(i), options, now))
+            .collect();
+        format!("<div class=\"tiles\">{tiles}</div>")
+    };
+    let group_names = config.group_names();
+    let mut sections = String::new();
+    if group_names.is_empty() {
+        sections.push_str(&tiles((0..config.builds.len()).collect()));
+    } else {
+        let in_group = |group: Option<&String>| {
+            (0..config.builds.len())
+                .filter(|i| {
+                    let groups = config.builds[*i].get_groups();
+                    match group {
+                        Some(group) => groups.contains(group),
+                        None => groups.is_empty(),
+                    }
+                })
+                .collect::<Vec<usize>>()
+        };
+        for group in group_names.iter() {
+            sections.push_str(&format!("<h2>{}</h2>", escape(group)));
+            sections.push_str(&tiles(in_group(Some(group))));
+        }
+        let ungrouped = in_group(None);
+        if !ungrouped.is_empty() {
+            sections.push_str(&format!("<h2>{UNGROUPED}</h2>"));
+            sections.push_str(&tiles(ungrouped));
+        }
+    }
+    let last_refresh = match snapshot.last_refresh {
+        Some(last_refresh) => format!("Last refresh {}", last_refresh.format("%H:%M:%S")),
+        None => String::from("Loading…"),
+    };
+    format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><meta http-equiv=\"refresh\" content=\"{PAGE_REFRESH_SECS}\"><title>rwatch</title><style>{STYLE}</style></head><body><header><span>rwatch</span><span>{last_refresh}</span></header>{sections}</body></html>"
+    )
+}
+
+async fn get_page(State(state): State<AppState>) -> Html<String> {
+    let snapshot = state.snapshot.read().unwrap();
+    Html(page(
+        &state.config,
+        &state.options,
+        &snapshot,
+        chrono::Utc::now(),
+    ))
+}
+
+/// Same rows as `--output json`, empty until the first poll completes
+async fn get_status(State(state): State<AppState>) -> impl IntoResponse {
+    let snapshot = state.snapshot.read().unwrap();
+    let body = serde_json::to_string(&ApiStatus {
+        last_refresh: snapshot.last_refresh.map(|d| d.to_rfc3339()),
+        builds: json_rows(&state.config.builds, &snapshot.results),
+    })
+    .unwrap();
+    ([(header::CONTENT_TYPE, "application/json")], body)
+}
+
+async fn get_cctray(State(state): State<AppState>) -> impl IntoResponse {
+    let snapshot = state.snapshot.read().unwrap();
+    let feed = cctray::to_feed(&state.config.builds, &snapshot.results);
+    ([(header::CONTENT_TYPE, "application/xml")], feed)
+}
+
+/// The only place fetching the builds, whatever the number of viewers
+async fn poll(state: AppState, mut transitions: Transitions) {
+    let config = &state.config;
+    let polling_interval = config.polling_interval.unwrap_or(DEFAULT_POLLING_INTERVAL);
+    let delay = Duration::from_millis(polling_interval);
+    loop {
+        // the previous results stay visible while requests are in flight
+        let results = config.fetch_all().await;
+        {
+            let mut snapshot = state.snapshot.write().unwrap();
+            let previous = std::mem::take(&mut snapshot.results);
+            snapshot.results = keep_rate_limited(previous, results);
+            transitions.update(config, &snapshot.results);
+            snapshot.last_refresh = Some(chrono::Local::now());
+        }
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// Dashboard for a wall screen, polling the builds in the background
+pub async fn serve(
+    config: Config,
+    options: RenderOptions,
+    transitions: Transitions,
+    listen: &str,
+    quiet: bool,
+) -> Result<(), Error> {
+    let state = AppState {
+        config: Arc::new(config),
+        options: Arc::new(options),
+        snapshot: Arc::new(RwLock::new(Snapshot::default())),
+    };
+    let listener = tokio::net::TcpListener::bind(listen)
+        .await
+        .map_err(|e| Error::Io(format!("cannot listen on {listen} : {e}")))?;
+    if !quiet {
+        println!("Serving the dashboard on http://{}", listener.local_addr()?);
+    }
+    tokio::spawn(poll(state.clone(), transitions));
+    let app = Router::new()
+        .route("/", get(get_page))
+        .route("/api/status", get(get_status))
+        .route("/cctray.xml", get(get_cctray))
+        .with_state(state);
+    axum::serve(listener, app).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod serve_tests {
+    use super::*;
+    use rwatch::rendering::OutputFormat;
+
+    fn config() -> Config {
+        serde_json::from_str(
+            "{\"builds\":[{\"tag\":\"bamboo\",\"serverUrl\":\"http://my.bamboo\",\"plan\":\"MY-PLAN\",\"groups\":[\"g1\"]},{\"tag\":\"exec\",\"title\":\"<tests>\",\"command\":\"make test\"}]}",
+        )
+        .unwrap()
+    }
+
+    fn options() -> RenderOptions {
+        RenderOptions {
+            format: OutputFormat::Table,
+            groups: None,
+            time_zone: Some(chrono_tz::UTC),
+            relative_times: false,
+            stale_after: None,
+        }
+    }
+
+    #[test]
+    fn tiles_by_group() {
+        let now = chrono::Utc::now();
+        let loading = page(&config(), &options(), &Snapshot::default(), now);
+        assert!(loading.contains("<span>Loading…</span>"));
+        assert!(
+            loading.contains("<a class=\"tile loading\" href=\"http://my.bamboo/browse/MY-PLAN\">")
+        );
+        let snapshot = Snapshot {
+            results: vec![
+                Ok(BuildStatus {
+                    status: Status::Red,
+                    url: String::from("http://my.bamboo/browse/MY-PLAN-12"),
+                    time_info: None,
+                    running: None,
+                }),
+                Err(Error::Timeout),
+            ],
+            last_refresh: Some(chrono::Local::now()),
+        };
+        let page = page(&config(), &options(), &snapshot, now);
+        let g1 = page.find("<h2>g1</h2>").unwrap();
+        let ungrouped = page.find("<h2>(no group)</h2>").unwrap();
+        let red = page
+            .find("<a class=\"tile red\" href=\"http://my.bamboo/browse/MY-PLAN-12\"><div class=\"title\">MY-PLAN</div><div class=\"details\">❌</div></a>")
+            .unwrap();
+        let error = page
+            .find(
+                "<div class=\"title\">&lt;tests&gt;</div><div class=\"details\">💣 timed out</div>",
+            )
+            .unwrap();
+        assert!(g1 < red && red < ungrouped && ungrouped < error);
+        // no external asset
+        assert!(!page.contains("src="));
+    }
+}
@@ -0,0 +1,160 @@
+use rwatch::{
+    build_status::{BuildStatus, Status},
+    config::Config,
+    error::Error,
+};
+
+use crate::{hooks, notify};
+
+/// What is tracked per build, to detect the transitions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum State {
+    Status(Status),
+    Failing,
+}
+
+impl State {
+    pub fn name(&self) -> &'static str {
+        match self {
+            State::Status(Status::Green) => "green",
+            State::Status(Status::Red) => "red",
+            State::Status(Status::Unstable) => "unstable",
+            State::Status(_) => "unknown",
+            State::Failing => "failing",
+        }
+    }
+}
+
+/// Only finished results count, a running or pending build keeps the
+/// state of its last result
+fn to_state(result: &Result<BuildStatus, Error>) -> Option<State> {
+    match result {
+        Ok(build_status) => match build_status.status {
+            Status::Green | Status::Red | Status::Unstable => {
+                Some(State::Status(build_status.status))
+            }
+            _ => None,
+        },
+        Err(_) => Some(State::Failing),
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Transition {
+    pub index: usize,
+    pub previous: State,
+    pub current: State,
+}
+
+/// Keeps the state of the builds between polls, to react to the transitions
+/// only : a build that stays red is notified once
+#[derive(Default)]
+pub struct Transitions {
+    states: Vec<Option<State>>,
+    /// Send desktop notifications
+    notify: bool,
+}
+
+impl Transitions {
+    pub fn new(notify: bool) -> Transitions {
+        Transitions {
+            states: Vec::new(),
+            notify,
+        }
+    }
+
+    fn transition(
+        &mut self,
+        index: usize,
+        result: &Result<BuildStatus, Error>,
+    ) -> Option<Transition> {
+        let current = to_state(result)?;
+        let previous = self.states[index].replace(current)?;
+        if previous == current {
+            return None;
+        }
+        Some(Transition {
+            index,
+            previous,
+            current,
+        })
+    }
+
+    /// Builds whose state changed since the previous poll, nothing has
+    /// changed on the first one
+    fn transitions(&mut self, results: &[Result<BuildStatus, Error>]) -> Vec<Transition> {
+        self.states.resize(results.len(), None);
+        results
+            .iter()
+            .enumerate()
+            .filter_map(|(index, result)| self.transition(index, result))
+            .collect()
+    }
+
+    fn react(&self, config: &Config, transition: &Transition, result: &Result<BuildStatus, Error>) {
+        let build = &config.builds[transition.index];
+        if self.notify {
+            notify::notify(&build.get_title(), transition.previous, result);
+        }
+        hooks::run(&config.hooks, build, transition, result);
+    }
+
+    /// Notify and run the hooks of the builds whose state changed
+    pub fn update(&mut self, config: &Config, results: &[Result<BuildStatus, Error>]) {
+        for transition in self.transitions(results) {
+            self.react(config, &transition, &results[transition.index]);
+        }
+    }
+
+    /// Same as `update`, for a single build refreshed on its own
+    pub fn update_one(
+        &mut self,
+        config: &Config,
+        index: usize,
+        result: &Result<BuildStatus, Error>,
+    ) {
+        if index >= self.states.len() {
+            return;
+        }
+        if let Some(transition) = self.transition(index, result) {
+            self.react(config, &transition, result);
+        }
+    }
+}
+
+#[cfg(test)]
+mod transitions_tests {
+    use super::*;
+
+    fn result(status: Status) -> Result<BuildStatus, Error> {
+        Ok(BuildStatus {
+            status,
+            url: String::from("http://my.bamboo/browse/MY-PLAN-12"),
+            time_info: None,
+            running: None,
+        })
+    }
+
+    #[test]
+    fn transitions_only() {
+        let mut transitions = Transitions::default();
+        let mut poll = |r: Result<BuildStatus, Error>| {
+            transitions
+                .transitions(&[r])
+                .into_iter()
+                .map(|t| format!("{} -> {}", t.previous.name(), t.current.name()))
+                .collect::<Vec<_>>()
+        };
+        // nothing on the first poll, even when red
+        assert!(poll(result(Status::Red)).is_empty());
+        assert!(poll(result(Status::Red)).is_empty());
+        // running builds keep their previous state
+        assert!(poll(result(Status::Running)).is_empty());
+        assert_eq!(poll(result(Status::Green)), vec!["red -> green"]);
+        assert!(poll(result(Status::Green)).is_empty());
+        assert_eq!(poll(Err(Error::Timeout)), vec!["green -> failing"]);
+        assert!(poll(Err(Error::NotFound)).is_empty());
+        assert_eq!(poll(result(Status::Green)), vec!["failing -> green"]);
+        assert_eq!(poll(result(Status::Unstable)), vec!["green -> unstable"]);
+    }
+}
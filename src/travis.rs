@@ -1,4 +1,10 @@
-use crate::build_status::{BuildStatus, Status, TimeInfo};
+use futures::{FutureExt, future::BoxFuture};
+
+use crate::{
+    build_status::{BuildStatus, RunningInfo, Status, TimeInfo},
+    error::Error,
+    provider::{Capabilities, Provider},
+};
 
 fn api_url(server_url: &str) -> String {
     // console.log("serverUrl", serverUrl);
@@ -14,17 +20,111 @@ struct TravisResponse {
     error_message: Option<String>,
 }
 
+#[derive(Debug, serde::Deserialize, PartialEq)]
+struct TravisBuilds {
+    builds: Vec<TravisBuild>,
+}
+
 #[derive(Debug, serde::Deserialize, PartialEq)]
 struct TravisBuild {
     state: String,
     id: u32,
-    previous_state: String,
-    finished_at: String,
-    duration: u32,
+    previous_state: Option<String>,
+    started_at: Option<String>,
+    finished_at: Option<String>,
+    duration: Option<u32>,
 }
 
-fn encode_uri_component(s: &str) -> String {
-    s.replace("/", "%2F")
+/// States of the builds that are over
+const FINISHED_STATES: &str = "passed,failed,errored,canceled";
+
+impl TravisBuild {
+    fn is_in_progress(&self) -> bool {
+        matches!(self.state.as_str(), "created" | "received" | "started")
+    }
+
+    fn url(&self, server_url: &str, repository: &str) -> String {
+        format!("{server_url}/{repository}/builds/{}", self.id)
+    }
+
+    fn to_running_info(
+        &self,
+        server_url: &str,
+        repository: &str,
+        now: chrono::DateTime<chrono::FixedOffset>,
+    ) -> Option<RunningInfo> {
+        if self.state != "started" {
+            return None;
+        }
+        let started_at = chrono::DateTime::parse_from_rfc3339(self.started_at.as_deref()?).ok()?;
+        Some(RunningInfo {
+            url: Some(self.url(server_url, repository)),
+            ..RunningInfo::new(started_at, now, None)
+        })
+    }
+
+    fn into_build_status(
+        self,
+        server_url: &str,
+        repository: &str,
+        now: chrono::DateTime<chrono::FixedOffset>,
+    ) -> BuildStatus {
+        let url = self.url(server_url, repository);
+        let running = self.to_running_info(server_url, repository, now);
+        let TravisBuild {
+            state,
+            previous_state,
+            finished_at,
+            duration,
+            ..
+        } = self;
+        let time_info = match (finished_at, duration) {
+            (Some(completed_at), Some(duration_secs)) => Some(TimeInfo {
+                completed_at,
+                duration_secs: Some(duration_secs),
+            }),
+            _ => None,
+        };
+        BuildStatus {
+            status: to_status(&state, previous_state.as_deref()),
+            url,
+            time_info,
+            running,
+        }
+    }
+}
+
+/// The last finished build gives the status and the link, along with the
+/// progress of the build in progress if any
+fn to_build_status(
+    last_build: TravisBuild,
+    finished: Option<TravisBuild>,
+    server_url: &str,
+    repository: &str,
+    now: chrono::DateTime<chrono::FixedOffset>,
+) -> BuildStatus {
+    match finished {
+        Some(finished) => BuildStatus {
+            running: last_build.to_running_info(server_url, repository, now),
+            ..finished.into_build_status(server_url, repository, now)
+        },
+        None => last_build.into_build_status(server_url, repository, now),
+    }
+}
+
+fn to_status(state: &str, previous_state: Option<&str>) -> Status {
+    match state {
+        "passed" => Status::Green,
+        "failed" | "errored" => Status::Red,
+        "canceled" => Status::Cancelled,
+        // report the last completed build while a new one is in progress
+        "created" | "received" | "started" => match previous_state {
+            Some(previous_state) => to_status(previous_state, None),
+            None if state == "started" => Status::Running,
+            None => Status::Pending,
+        },
+        _ => Status::Unknown,
+    }
 }
 
 pub async fn fetch(
@@ -32,10 +132,10 @@ pub async fn fetch(
     repository: &String,
     branch: &String,
     token: &Option<String>,
-) -> Result<BuildStatus, String> {
+) -> Result<BuildStatus, Error> {
     let api_url = api_url(server_url);
-    let repository = encode_uri_component(repository);
-    let branch = encode_uri_component(branch);
+    let repository = crate::utils::encode_uri_component(repository);
+    let branch = crate::utils::encode_uri_component(branch);
     let url = format!("{api_url}/repo/{repository}/branch/{branch}");
     let mut headers = vec![
         (String::from("Accept"), String::from("application/json")),
@@ -48,42 +148,156 @@ pub async fn fetch(
     if let Some(t) = token {
         headers.push((String::from("Authorization"), format!("token {t}")));
     }
-    crate::utils::request::<TravisResponse>(&url, &headers)
-        .await
-        .and_then(|response| match response.last_build {
-            Some(TravisBuild {
-                id,
-                state,
-                previous_state,
-                finished_at,
-                duration,
-            }) => {
-                let url = format!("{server_url}/{repository}/builds/{id}");
-                let mut state = state.as_str();
-                if state == "started" || state == "created" {
-                    state = &previous_state;
-                }
-                let time_info = Some(TimeInfo {
-                    completed_at: finished_at,
-                    duration_secs: duration,
-                });
-                match state {
-                    "passed" => Ok(BuildStatus {
-                        status: Status::Green,
-                        url,
-                        time_info,
-                    }),
-                    "failed" | "errored" => Ok(BuildStatus {
-                        status: Status::Red,
-                        url,
-                        time_info,
-                    }),
-                    _ => Err(format!("unhandled state : {state}")),
-                }
-            }
-            None => match response.error_message {
-                Some(error_message) => Err(error_message),
-                None => Err(String::from("No error message available")),
-            },
-        })
+    let response = crate::utils::request::<TravisResponse>(&url, &headers).await?;
+    let last_build = match response.last_build {
+        Some(build) => build,
+        None => {
+            return Err(match response.error_message {
+                Some(error_message) => Error::Provider(error_message),
+                None => Error::NoBuild,
+            });
+        }
+    };
+    // the branch only tells the state of the previous build, not its link
+    let finished = if last_build.is_in_progress() && last_build.previous_state.is_some() {
+        let builds_url = format!(
+            "{api_url}/repo/{repository}/builds?branch.name={branch}&state={FINISHED_STATES}&limit=1"
+        );
+        crate::utils::request::<TravisBuilds>(&builds_url, &headers)
+            .await?
+            .builds
+            .into_iter()
+            .next()
+    } else {
+        None
+    };
+    Ok(to_build_status(
+        last_build,
+        finished,
+        server_url,
+        &repository,
+        chrono::Utc::now().fixed_offset(),
+    ))
+}
+
+/// The last build of a branch
+#[derive(Debug, serde::Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TravisConfig {
+    pub server_url: String,
+    pub repository: String,
+    pub branch: String,
+    pub token: Option<String>,
+}
+
+impl Provider for TravisConfig {
+    fn tag(&self) -> &'static str {
+        "travis"
+    }
+
+    fn title(&self) -> String {
+        format!("{}/{}", self.repository, self.branch)
+    }
+
+    fn web_url(&self) -> String {
+        format!("{}/{}/branches", self.server_url, self.repository)
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            time_info: true,
+            running: true,
+        }
+    }
+
+    fn fetch(&self) -> BoxFuture<'_, Result<BuildStatus, Error>> {
+        fetch(
+            &self.server_url,
+            &self.repository,
+            &self.branch,
+            &self.token,
+        )
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod travis_tests {
+    use super::*;
+
+    #[test]
+    fn map_states() {
+        assert_eq!(to_status("passed", None), Status::Green);
+        assert_eq!(to_status("errored", None), Status::Red);
+        assert_eq!(to_status("canceled", Some("passed")), Status::Cancelled);
+        assert_eq!(to_status("started", Some("failed")), Status::Red);
+        assert_eq!(to_status("started", None), Status::Running);
+        assert_eq!(to_status("created", None), Status::Pending);
+        assert_eq!(to_status("created", Some("started")), Status::Running);
+        assert_eq!(to_status("whatever", None), Status::Unknown);
+    }
+
+    #[test]
+    fn convert_started() {
+        let build = TravisBuild {
+            state: String::from("started"),
+            id: 1234,
+            previous_state: Some(String::from("failed")),
+            started_at: Some(String::from("2025-11-07T08:01:40Z")),
+            finished_at: None,
+            duration: None,
+        };
+        let now = chrono::DateTime::parse_from_rfc3339("2025-11-07T08:11:40Z").unwrap();
+        let expected = BuildStatus {
+            status: Status::Red,
+            url: String::from("https://my.travis/my%2Frepo/builds/1234"),
+            time_info: None,
+            running: Some(RunningInfo {
+                started_at: Some(String::from("2025-11-07T08:01:40+00:00")),
+                elapsed_secs: Some(600),
+                estimated_secs: None,
+                url: Some(String::from("https://my.travis/my%2Frepo/builds/1234")),
+            }),
+        };
+        assert_eq!(
+            build.into_build_status("https://my.travis", "my%2Frepo", now),
+            expected
+        );
+    }
+
+    #[test]
+    fn convert_started_after_finished() {
+        let builds = serde_json::from_str::<TravisBuilds>(
+            "{\"builds\":[{\"id\":1233,\"state\":\"failed\",\"previous_state\":\"passed\",\"started_at\":\"2025-11-07T07:00:00Z\",\"finished_at\":\"2025-11-07T07:10:00Z\",\"duration\":600}]}",
+        )
+        .unwrap();
+        let running = TravisBuild {
+            state: String::from("started"),
+            id: 1234,
+            previous_state: Some(String::from("failed")),
+            started_at: Some(String::from("2025-11-07T08:01:40Z")),
+            finished_at: None,
+            duration: None,
+        };
+        let now = chrono::DateTime::parse_from_rfc3339("2025-11-07T08:11:40Z").unwrap();
+        let expected = BuildStatus {
+            status: Status::Red,
+            url: String::from("https://my.travis/my%2Frepo/builds/1233"),
+            time_info: Some(TimeInfo {
+                completed_at: String::from("2025-11-07T07:10:00Z"),
+                duration_secs: Some(600),
+            }),
+            running: Some(RunningInfo {
+                started_at: Some(String::from("2025-11-07T08:01:40+00:00")),
+                elapsed_secs: Some(600),
+                estimated_secs: None,
+                url: Some(String::from("https://my.travis/my%2Frepo/builds/1234")),
+            }),
+        };
+        let finished = builds.builds.into_iter().next();
+        assert_eq!(
+            to_build_status(running, finished, "https://my.travis", "my%2Frepo", now),
+            expected
+        );
+    }
 }
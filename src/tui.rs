@@ -0,0 +1,503 @@
+use std::{sync::Arc, time::Duration};
+
+use ratatui::{
+    DefaultTerminal, Frame,
+    crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    layout::{Constraint, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Cell, Paragraph, Row, Table, TableState},
+};
+use tokio::sync::mpsc::UnboundedSender;
+
+use rwatch::{
+    build_status::{BuildStatus, Status},
+    config::Config,
+    error::Error,
+    rendering::{RenderOptions, RowData},
+};
+
+use crate::{
+    transitions::Transitions,
+    watch::{DEFAULT_POLLING_INTERVAL, keep_if_rate_limited},
+};
+
+const HELP: &str = "↵ open  r/R refresh  / filter  g group  q quit";
+
+enum Message {
+    Terminal(Event),
+    /// Results of all the builds, in the order of the config
+    Fetched(Vec<Result<BuildStatus, Error>>),
+    /// Result of a single build, refreshed on demand
+    FetchedOne(usize, Result<BuildStatus, Error>),
+}
+
+struct App {
+    config: Arc<Config>,
+    options: RenderOptions,
+    transitions: Transitions,
+    group_names: Vec<String>,
+    /// `None` until the build is fetched for the first time
+    results: Vec<Option<Result<BuildStatus, Error>>>,
+    fetching: Vec<bool>,
+    fetching_all: bool,
+    table: TableState,
+    filter: String,
+    editing_filter: bool,
+    /// Index in `group_names`, all builds when `None`
+    group: Option<usize>,
+    last_refresh: Option<chrono::DateTime<chrono::Local>>,
+    polling_interval: Duration,
+    next_poll: tokio::time::Instant,
+    /// Feedback of the last action, e.g. a URL that could not be opened
+    message: Option<String>,
+    quit: bool,
+}
+
+fn status_color(status: &Status) -> Color {
+    match status {
+        Status::Green => Color::Green,
+        Status::Red => Color::Red,
+        Status::Running => Color::Cyan,
+        Status::Pending => Color::Gray,
+        Status::Cancelled => Color::DarkGray,
+        Status::Unstable => Color::Yellow,
+        Status::Unknown => Color::Magenta,
+    }
+}
+
+impl App {
+    fn new(config: Arc<Config>, options: RenderOptions, transitions: Transitions) -> App {
+        let len = config.builds.len();
+        let polling_interval =
+            Duration::from_millis(config.polling_interval.unwrap_or(DEFAULT_POLLING_INTERVAL));
+        App {
+            group_names: config.group_names(),
+            config,
+            options,
+            transitions,
+            results: (0..len).map(|_| None).collect(),
+            fetching: vec![false; len],
+            fetching_all: false,
+            table: TableState::default().with_selected(Some(0)),
+            filter: String::new(),
+            editing_filter: false,
+            group: None,
+            last_refresh: None,
+            polling_interval,
+            next_poll: tokio::time::Instant::now() + polling_interval,
+            message: None,
+            quit: false,
+        }
+    }
+
+    /// Indices of the builds matching the group and filter, sorted by title
+    /// like the table
+    fn visible(&self) -> Vec<usize> {
+        let filter = self.filter.to_lowercase();
+        let group = self.group.map(|i| &self.group_names[i]);
+        let mut visible: Vec<(String, usize)> = self
+            .config
+            .builds
+            .iter()
+            .enumerate()
+            .filter(|(_, build)| group.is_none_or(|g| build.get_groups().contains(g)))
+            .map(|(i, build)| (build.get_title(), i))
+            .filter(|(title, _)| title.to_lowercase().contains(&filter))
+            .collect();
+        visible.sort();
+        visible.into_iter().map(|(_, i)| i).collect()
+    }
+
+    fn selected_build(&self) -> Option<usize> {
+        self.table
+            .selected()
+            .and_then(|row| self.visible().get(row).copied())
+    }
+
+    fn refresh_all(&mut self, tx: &UnboundedSender<Message>) {
+        self.next_poll = tokio::time::Instant::now() + self.polling_interval;
+        if self.fetching_all {
+            return;
+        }
+        self.fetching_all = true;
+        self.fetching.iter_mut().for_each(|f| *f = true);
+        let config = self.config.clone();
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            let _ = tx.send(Message::Fetched(config.fetch_all().await));
+        });
+    }
+
+    fn refresh_one(&mut self, index: usize, tx: &UnboundedSender<Message>) {
+        if self.fetching[index] {
+            return;
+        }
+        self.fetching[index] = true;
+        let config = self.config.clone();
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            let result = config.builds[index].fetch().await;
+            let _ = tx.send(Message::FetchedOne(index, result));
+        });
+    }
+
+    fn open_selected(&mut self) {
+        let Some(index) = self.selected_build() else {
+            return;
+        };
+        // the page of the build on the CI when its status is not available
+        let url = match &self.results[index] {
+            Some(Ok(build_status)) => build_status.url.clone(),
+            _ => self.config.builds[index].provider().web_url(),
+        };
+        self.message = match open::that_detached(&url) {
+            Ok(()) => Some(format!("Opened {url}")),
+            Err(e) => Some(format!("Cannot open {url} : {e}")),
+        };
+    }
+
+    fn cycle_group(&mut self) {
+        self.group = match self.group {
+            None if !self.group_names.is_empty() => Some(0),
+            Some(i) if i + 1 < self.group_names.len() => Some(i + 1),
+            _ => None,
+        };
+        self.table.select(Some(0));
+    }
+
+    fn handle_key(&mut self, key: KeyEvent, tx: &UnboundedSender<Message>) {
+        if key.kind != KeyEventKind::Press {
+            return;
+        }
+        if self.editing_filter {
+            match key.code {
+                KeyCode::Char(c) => self.filter.push(c),
+                KeyCode::Backspace => {
+                    self.filter.pop();
+                }
+                KeyCode::Enter => self.editing_filter = false,
+                KeyCode::Esc => {
+                    self.filter.clear();
+                    self.editing_filter = false;
+                }
+                _ => {}
+            }
+            self.table.select(Some(0));
+            return;
+        }
+        self.message = None;
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => self.quit = true,
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Down | KeyCode::Char('j') => self.table.select_next(),
+            KeyCode::Up | KeyCode::Char('k') => self.table.select_previous(),
+            KeyCode::Home => self.table.select_first(),
+            KeyCode::End => self.table.select_last(),
+            KeyCode::PageDown => self.table.scroll_down_by(10),
+            KeyCode::PageUp => self.table.scroll_up_by(10),
+            KeyCode::Enter => self.open_selected(),
+            KeyCode::Char('r') => {
+                if let Some(index) = self.selected_build() {
+                    self.refresh_one(index, tx);
+                }
+            }
+            KeyCode::Char('R') => self.refresh_all(tx),
+            KeyCode::Char('/') => self.editing_filter = true,
+            KeyCode::Char('g') => self.cycle_group(),
+            _ => {}
+        }
+    }
+
+    fn update(&mut self, message: Message, tx: &UnboundedSender<Message>) {
+        match message {
+            Message::Terminal(Event::Key(key)) => self.handle_key(key, tx),
+            // anything else, like a resize, only needs a redraw
+            Message::Terminal(_) => {}
+            Message::Fetched(results) => {
+                // the tiles of a rate limited server keep their last status
+                let results: Vec<Result<BuildStatus, Error>> = std::mem::take(&mut self.results)
+                    .into_iter()
+                    .zip(results)
+                    .map(|(previous, result)| keep_if_rate_limited(previous, result))
+                    .collect();
+                self.transitions.update(&self.config, &results);
+                self.results = results.into_iter().map(Some).collect();
+                self.fetching.iter_mut().for_each(|f| *f = false);
+                self.fetching_all = false;
+                self.last_refresh = Some(chrono::Local::now());
+            }
+            Message::FetchedOne(index, result) => {
+                let result = keep_if_rate_limited(self.results[index].take(), result);
+                self.transitions.update_one(&self.config, index, &result);
+                self.results[index] = Some(result);
+                self.fetching[index] = false;
+            }
+        }
+    }
+
+    fn row(&self, index: usize, now: chrono::DateTime<chrono::Utc>) -> Row<'static> {
+        let build = &self.config.builds[index];
+        let cells = match &self.results[index] {
+            None => vec![
+                Cell::from("…"),
+                Cell::from(build.get_title()),
+                Cell::from(""),
+                Cell::from(""),
+            ],
+            // the error is too long for the other columns
+            Some(Err(e)) => vec![
+                Cell::from("💣"),
+                Cell::from(Line::from(vec![
+                    Span::raw(format!("{}  ", build.get_title())),
+                    Span::styled(e.to_string(), Style::default().fg(Color::Red)),
+                ])),
+                Cell::from(""),
+                Cell::from(""),
+            ],
+            Some(Ok(build_status)) => {
+                let row = RowData::new(build, build_status, &self.options, now);
+                let status = match &row.progress {
+                    Some(progress) => format!("{} → ⏳ {progress}", row.status),
+                    None => row.status.to_string(),
+                };
+                let completed_at_style = if row.stale {
+                    Style::default().fg(Color::Yellow)
+                } else {
+                    Style::default()
+                };
+                vec![
+                    Cell::from(status)
+                        .style(Style::default().fg(status_color(&build_status.status))),
+                    Cell::from(row.title),
+                    Cell::from(row.completed_at).style(completed_at_style),
+                    Cell::from(row.duration),
+                ]
+            }
+        };
+        let style = if self.fetching[index] {
+            Style::default().add_modifier(Modifier::DIM)
+        } else {
+            Style::default()
+        };
+        Row::new(cells).style(style)
+    }
+
+    fn status_bar(&self) -> String {
+        let mut parts = Vec::new();
+        match self.last_refresh {
+            Some(last_refresh) => {
+                let next = self
+                    .next_poll
+                    .saturating_duration_since(tokio::time::Instant::now());
+                parts.push(format!(
+                    "Last refresh {}, next in {}s",
+                    last_refresh.format("%H:%M:%S"),
+                    next.as_secs()
+                ));
+            }
+            None => parts.push(String::from("Loading…")),
+        }
+        if let Some(group) = self.group {
+            parts.push(format!("group: {}", self.group_names[group]));
+        }
+        if self.editing_filter {
+            parts.push(format!("filter: {}_", self.filter));
+        } else if !self.filter.is_empty() {
+            parts.push(format!("filter: {}", self.filter));
+        }
+        parts.push(self.message.clone().unwrap_or(String::from(HELP)));
+        parts.join(" | ")
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [table_area, status_area] =
+            Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+        let now = chrono::Utc::now();
+        let rows: Vec<Row> = self
+            .visible()
+            .into_iter()
+            .map(|index| self.row(index, now))
+            .collect();
+        let header = Row::new(["", "Build", "Completed", "Duration"])
+            .style(Style::default().add_modifier(Modifier::BOLD));
+        let widths = [
+            Constraint::Length(16),
+            Constraint::Fill(1),
+            Constraint::Length(19),
+            Constraint::Length(20),
+        ];
+        let table = Table::new(rows, widths)
+            .header(header)
+            .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(table, table_area, &mut self.table);
+        frame.render_widget(
+            Paragraph::new(Line::from(self.status_bar())).style(Style::default().fg(Color::Gray)),
+            status_area,
+        );
+    }
+}
+
+/// Read the terminal events on a dedicated thread, as crossterm blocks
+fn read_events(tx: UnboundedSender<Message>) {
+    std::thread::spawn(move || {
+        while let Ok(event) = event::read() {
+            if tx.send(Message::Terminal(event)).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+async fn run(terminal: &mut DefaultTerminal, mut app: App) -> Result<(), Error> {
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    read_events(tx.clone());
+    app.refresh_all(&tx);
+    // redraw every second, for the countdown and the running builds
+    let mut tick = tokio::time::interval(Duration::from_secs(1));
+    while !app.quit {
+        terminal.draw(|frame| app.draw(frame))?;
+        tokio::select! {
+            Some(message) = rx.recv() => app.update(message, &tx),
+            _ = tokio::time::sleep_until(app.next_poll) => app.refresh_all(&tx),
+            _ = tick.tick() => {}
+        }
+    }
+    Ok(())
+}
+
+/// Full-screen interactive UI, polling the builds at the configured interval
+pub async fn tui(
+    config: Config,
+    options: RenderOptions,
+    transitions: Transitions,
+) -> Result<(), Error> {
+    let app = App::new(Arc::new(config), options, transitions);
+    let mut terminal = ratatui::init();
+    let result = run(&mut terminal, app).await;
+    ratatui::restore();
+    result
+}
+
+#[cfg(test)]
+mod tui_tests {
+    use super::*;
+    use rwatch::{
+        bamboo::BambooConfig,
+        config::{BuildConfig, DisplayConfig, HttpConfig, ProviderConfig},
+        rendering::OutputFormat,
+    };
+
+    fn bamboo(plan: &str, groups: &[&str]) -> BuildConfig {
+        BuildConfig {
+            provider: ProviderConfig::Bamboo(BambooConfig {
+                server_url: String::from("http://my.bamboo"),
+                plan: String::from(plan),
+                token: None,
+            }),
+            timeout_secs: None,
+            groups: groups.iter().map(|g| g.to_string()).collect(),
+        }
+    }
+
+    fn app() -> App {
+        let config = Config {
+            polling_interval: None,
+            http: HttpConfig::default(),
+            display: DisplayConfig::default(),
+            hooks: vec![],
+            builds: vec![
+                bamboo("WEB-MAIN", &["web"]),
+                bamboo("API-MAIN", &["api"]),
+                bamboo("WEB-NIGHTLY", &["web", "nightly"]),
+            ],
+        };
+        let options = RenderOptions {
+            format: OutputFormat::Table,
+            groups: None,
+            time_zone: None,
+            relative_times: false,
+            stale_after: None,
+        };
+        App::new(Arc::new(config), options, Transitions::default())
+    }
+
+    fn press(app: &mut App, code: KeyCode) {
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+        app.handle_key(KeyEvent::from(code), &tx);
+    }
+
+    #[test]
+    fn filter_builds() {
+        let mut app = app();
+        assert_eq!(app.visible(), vec![1, 0, 2]);
+        for code in [KeyCode::Char('/'), KeyCode::Char('w'), KeyCode::Char('E')] {
+            press(&mut app, code);
+        }
+        assert!(app.editing_filter);
+        assert_eq!(app.visible(), vec![0, 2]);
+        assert_eq!(app.status_bar(), format!("Loading… | filter: wE_ | {HELP}"));
+        press(&mut app, KeyCode::Enter);
+        assert!(!app.editing_filter);
+        assert_eq!(app.selected_build(), Some(0));
+        press(&mut app, KeyCode::Char('/'));
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.visible(), vec![1, 0, 2]);
+        assert!(!app.quit);
+        press(&mut app, KeyCode::Char('q'));
+        assert!(app.quit);
+    }
+
+    #[test]
+    fn keep_rate_limited_tiles() {
+        let mut app = app();
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+        let green = || {
+            Ok(BuildStatus {
+                status: Status::Green,
+                url: String::from("http://my.bamboo/browse/WEB-MAIN-1"),
+                time_info: None,
+                running: None,
+            })
+        };
+        let rate_limited = || {
+            Err(Error::RateLimited {
+                retry_after_secs: Some(60),
+            })
+        };
+        app.update(
+            Message::Fetched(vec![green(), rate_limited(), Err(Error::Timeout)]),
+            &tx,
+        );
+        app.update(
+            Message::Fetched(vec![rate_limited(), rate_limited(), rate_limited()]),
+            &tx,
+        );
+        assert_eq!(
+            app.results,
+            vec![Some(green()), Some(rate_limited()), Some(rate_limited())]
+        );
+        app.update(Message::FetchedOne(0, rate_limited()), &tx);
+        assert_eq!(app.results[0], Some(green()));
+        app.update(Message::FetchedOne(0, Err(Error::NotFound)), &tx);
+        assert_eq!(app.results[0], Some(Err(Error::NotFound)));
+    }
+
+    #[test]
+    fn cycle_groups() {
+        let mut app = app();
+        assert_eq!(app.group_names, vec!["web", "api", "nightly"]);
+        press(&mut app, KeyCode::Char('g'));
+        assert_eq!(app.visible(), vec![0, 2]);
+        press(&mut app, KeyCode::Char('g'));
+        assert_eq!(app.visible(), vec![1]);
+        press(&mut app, KeyCode::Char('g'));
+        assert_eq!(app.visible(), vec![2]);
+        assert_eq!(
+            app.status_bar(),
+            format!("Loading… | group: nightly | {HELP}")
+        );
+        press(&mut app, KeyCode::Char('g'));
+        assert_eq!(app.visible(), vec![1, 0, 2]);
+    }
+}
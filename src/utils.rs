@@ -1,64 +1,408 @@
-use reqwest::RequestBuilder;
+use std::{
+    collections::HashMap,
+    hash::BuildHasher,
+    sync::{Arc, LazyLock, Mutex, OnceLock},
+    time::{Duration, Instant, SystemTime},
+};
+
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, utf8_percent_encode};
+use reqwest::{RequestBuilder, header::HeaderMap};
+use tokio::sync::Semaphore;
+
+use crate::{
+    config::{DEFAULT_MAX_REQUESTS_PER_SERVER, HttpConfig, RetryConfig},
+    error::Error,
+};
+
+/// Clients shared by all requests, so that connections are pooled
+struct Http {
+    default: reqwest::Client,
+    /// Only built when some servers accept invalid certificates
+    insecure: Option<reqwest::Client>,
+    insecure_servers: Vec<String>,
+    retry: RetryConfig,
+    max_requests_per_server: usize,
+    server_limits: HashMap<String, usize>,
+}
+
+static HTTP: OnceLock<Http> = OnceLock::new();
+
+/// Servers (origins) that rate limited us, with the time until which they
+/// are not called anymore
+static RATE_LIMITED: LazyLock<Mutex<HashMap<String, Instant>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Requests in flight, per server
+static SERVER_BUDGETS: LazyLock<Mutex<HashMap<String, Arc<Semaphore>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn client_builder(http: &HttpConfig) -> Result<reqwest::ClientBuilder, Error> {
+    let mut builder = reqwest::Client::builder();
+    if let Some(secs) = http.timeout_secs {
+        builder = builder.timeout(Duration::from_secs(secs));
+    }
+    // without a proxy in the config, reqwest uses the env variables
+    if let Some(proxy) = &http.proxy {
+        let no_proxy = match &http.no_proxy {
+            Some(no_proxy) => reqwest::NoProxy::from_string(no_proxy),
+            None => reqwest::NoProxy::from_env(),
+        };
+        let proxy = reqwest::Proxy::all(proxy)
+            .map_err(|e| Error::Config(format!("invalid proxy {proxy} : {e}")))?
+            .no_proxy(no_proxy);
+        builder = builder.proxy(proxy);
+    }
+    for path in http.ca_certificates.iter() {
+        let pem = std::fs::read(path)
+            .map_err(|e| Error::Config(format!("cannot read certificate {path} : {e}")))?;
+        let certificates = reqwest::Certificate::from_pem_bundle(&pem)
+            .map_err(|e| Error::Config(format!("invalid certificate {path} : {e}")))?;
+        for certificate in certificates {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+    Ok(builder)
+}
+
+fn build_http(http: &HttpConfig) -> Result<Http, Error> {
+    let build = |builder: reqwest::ClientBuilder| {
+        builder
+            .build()
+            .map_err(|e| Error::Config(format!("cannot create HTTP client : {e}")))
+    };
+    let default = build(client_builder(http)?)?;
+    let insecure = if http.accept_invalid_certs.is_empty() {
+        None
+    } else {
+        Some(build(
+            client_builder(http)?.danger_accept_invalid_certs(true),
+        )?)
+    };
+    Ok(Http {
+        default,
+        insecure,
+        insecure_servers: http.accept_invalid_certs.clone(),
+        retry: http.retry.clone(),
+        max_requests_per_server: http
+            .max_requests_per_server
+            .unwrap_or(DEFAULT_MAX_REQUESTS_PER_SERVER),
+        server_limits: http.server_limits.clone(),
+    })
+}
+
+/// Create the shared HTTP clients, before sending any request
+pub fn init_http(http: &HttpConfig) -> Result<(), Error> {
+    let http = build_http(http)?;
+    HTTP.set(http)
+        .map_err(|_| Error::Config(String::from("HTTP clients already initialized")))
+}
+
+/// Report invalid proxies or certificates without creating the shared clients
+pub fn check_http_config(http: &HttpConfig) -> Result<(), Error> {
+    build_http(http).map(|_| ())
+}
+
+fn http() -> &'static Http {
+    HTTP.get_or_init(|| {
+        build_http(&HttpConfig::default()).expect("cannot create default HTTP client")
+    })
+}
+
+/// Whether the URL is on the server : same scheme, host and port, and under
+/// the path of the server if it has one, e.g. `https://my.jenkins/ci`
+fn is_on_server(url: &str, server: &str) -> bool {
+    let (Ok(url), Ok(server)) = (reqwest::Url::parse(url), reqwest::Url::parse(server)) else {
+        return false;
+    };
+    if url.scheme() != server.scheme()
+        || url.host() != server.host()
+        || url.port_or_known_default() != server.port_or_known_default()
+    {
+        return false;
+    }
+    let prefix = server.path().trim_end_matches('/');
+    let path = url.path();
+    prefix.is_empty()
+        || path == prefix
+        || path
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+fn client_for(url: &str) -> &'static reqwest::Client {
+    let http = http();
+    match &http.insecure {
+        Some(insecure) if http.insecure_servers.iter().any(|s| is_on_server(url, s)) => insecure,
+        _ => &http.default,
+    }
+}
+
+/// Runs a command line through the shell of the platform. On Unix it gets
+/// its own process group, see `ProcessGroup`.
+pub fn shell(command: &str) -> tokio::process::Command {
+    let mut shell = if cfg!(windows) {
+        let mut shell = tokio::process::Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = tokio::process::Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    shell.arg(command);
+    #[cfg(unix)]
+    shell.process_group(0);
+    shell
+}
+
+/// Kills the process group of a command started by `shell` when dropped,
+/// e.g. on timeout. `kill_on_drop` only kills the shell, not what it started
+/// like `make test` or the commands of a pipeline.
+pub struct ProcessGroup(Option<u32>);
+
+impl ProcessGroup {
+    pub fn new(child: &tokio::process::Child) -> ProcessGroup {
+        ProcessGroup(child.id())
+    }
+
+    /// The command completed, there is nothing to kill
+    pub fn disarm(mut self) {
+        self.0 = None;
+    }
+}
+
+impl Drop for ProcessGroup {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if let Some(pid) = self.0.and_then(|pid| libc::pid_t::try_from(pid).ok()) {
+            // the group is led by the shell, its pid is the group id
+            unsafe {
+                libc::killpg(pid, libc::SIGKILL);
+            }
+        }
+    }
+}
+
+/// Characters left as is by JavaScript's `encodeURIComponent`
+const URI_COMPONENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'!')
+    .remove(b'~')
+    .remove(b'*')
+    .remove(b'\'')
+    .remove(b'(')
+    .remove(b')');
+
+/// Percent-encode a path segment or a query value, e.g. `my/project`
+pub fn encode_uri_component(s: &str) -> String {
+    utf8_percent_encode(s, URI_COMPONENT).to_string()
+}
 
 fn build_request(url: &str, headers: &Vec<(String, String)>) -> RequestBuilder {
-    let client = reqwest::Client::new();
-    let mut builder = client.get(url);
+    let mut builder = client_for(url).get(url);
     for (key, value) in headers {
         builder = builder.header(key, value);
     }
     builder
 }
 
-async fn send_request(
-    url: &str,
-    headers: &Vec<(String, String)>,
-) -> Result<reqwest::Response, reqwest::Error> {
-    return build_request(url, headers).send().await;
+fn header_value<'a>(headers: &'a HeaderMap, names: &[&str]) -> Option<&'a str> {
+    names
+        .iter()
+        .find_map(|name| headers.get(*name))
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.trim())
+}
+
+/// GitHub (`X-RateLimit-*`) and GitLab (`RateLimit-*`) report the remaining
+/// requests. GitHub answers 403 instead of 429 when they are exhausted.
+fn is_rate_limit_exhausted(headers: &HeaderMap) -> bool {
+    header_value(headers, &["x-ratelimit-remaining", "ratelimit-remaining"]) == Some("0")
+}
+
+/// Delay asked by the server : `Retry-After`, or the reset time of an exhausted
+/// rate limit. `Retry-After` can also be an HTTP date, only seconds are supported.
+fn retry_after_secs(headers: &HeaderMap, now_epoch_secs: u64) -> Option<u64> {
+    if let Some(secs) = header_value(headers, &["retry-after"]).and_then(|v| v.parse().ok()) {
+        return Some(secs);
+    }
+    if !is_rate_limit_exhausted(headers) {
+        return None;
+    }
+    header_value(headers, &["x-ratelimit-reset", "ratelimit-reset"])
+        .and_then(|v| v.parse::<u64>().ok())
+        .map(|reset| reset.saturating_sub(now_epoch_secs))
+}
+
+fn epoch_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
 }
 
-async fn send_request_basic(
+/// Rate limits are 429, or 403 with exhausted GitHub limits. A 503 asking
+/// to retry later is handled the same way.
+fn status_error(status: u16, headers: &HeaderMap) -> Error {
+    let retry_after = header_value(headers, &["retry-after"]).is_some();
+    if status == 429
+        || (status == 403 && is_rate_limit_exhausted(headers))
+        || (status == 503 && retry_after)
+    {
+        Error::RateLimited {
+            retry_after_secs: retry_after_secs(headers, epoch_secs()),
+        }
+    } else {
+        Error::from_status(status, None)
+    }
+}
+
+fn handle_status(r: Result<reqwest::Response, reqwest::Error>) -> Result<reqwest::Response, Error> {
+    r.map_err(Error::from).and_then(|r| {
+        if r.status().is_success() {
+            Ok(r)
+        } else {
+            Err(status_error(r.status().as_u16(), r.headers()))
+        }
+    })
+}
+
+/// Exponential backoff before the nth retry (starting at 1), with jitter :
+/// somewhere between half and the full delay
+fn backoff(retry: &RetryConfig, n: u32, random: u64) -> Duration {
+    let factor = 1u64 << n.saturating_sub(1).min(32);
+    let delay = retry
+        .initial_backoff_ms
+        .saturating_mul(factor)
+        .min(retry.max_backoff_ms);
+    let half = delay / 2;
+    Duration::from_millis(half + random % (delay - half + 1))
+}
+
+/// Good enough randomness for the jitter, without an extra dependency
+fn random() -> u64 {
+    std::collections::hash_map::RandomState::new().hash_one(Instant::now())
+}
+
+fn origin(url: &str) -> Option<String> {
+    reqwest::Url::parse(url)
+        .ok()
+        .map(|u| u.origin().ascii_serialization())
+}
+
+/// Time left before calling a server that rate limited us
+fn rate_limit_remaining(origin: &str) -> Option<Duration> {
+    let mut limited = RATE_LIMITED.lock().unwrap();
+    let until = *limited.get(origin)?;
+    let now = Instant::now();
+    if until > now {
+        Some(until - now)
+    } else {
+        limited.remove(origin);
+        None
+    }
+}
+
+fn set_rate_limited(origin: &str, delay: Duration) {
+    RATE_LIMITED
+        .lock()
+        .unwrap()
+        .insert(origin.to_string(), Instant::now() + delay);
+}
+
+/// Key and limit of the budget of a server : its origin, with the limit
+/// configured for this origin or the default one
+fn server_budget(
     url: &str,
-    headers: &Vec<(String, String)>,
-    user: String,
-    password: Option<String>,
-) -> Result<reqwest::Response, reqwest::Error> {
-    return build_request(url, headers)
-        .basic_auth(user, password)
-        .send()
-        .await;
-}
-
-fn handle_status(
-    r: Result<reqwest::Response, reqwest::Error>,
-) -> Result<reqwest::Response, String> {
-    r.map_err(|e| format!("Request error {:?}", e))
-        .and_then(|r| {
-            let status = r.status().as_u16();
-            if status == 200 {
-                Ok(r)
-            } else {
-                Err(format!("Invalid status {status}"))
-            }
-        })
+    default_limit: usize,
+    server_limits: &HashMap<String, usize>,
+) -> (String, usize) {
+    let key = origin(url).unwrap_or(url.to_string());
+    let limit = server_limits
+        .iter()
+        .filter(|(server, _)| origin(server).as_ref() == Some(&key))
+        .map(|(_, limit)| *limit)
+        .min()
+        .unwrap_or(default_limit);
+    (key, limit)
 }
 
-async fn resp_to_json<T: serde::de::DeserializeOwned>(
-    r: Result<reqwest::Response, String>,
-) -> Result<T, String> {
-    match r {
-        Ok(response) => response
-            .json::<T>()
-            .await
-            .map_err(|e| format!("JSON Decode error : {:?}", e)),
-        Err(e) => Err(e),
+fn server_semaphore(url: &str) -> Arc<Semaphore> {
+    let http = http();
+    let (key, limit) = server_budget(url, http.max_requests_per_server, &http.server_limits);
+    SERVER_BUDGETS
+        .lock()
+        .unwrap()
+        .entry(key)
+        // a budget of 0 would never give a permit
+        .or_insert_with(|| Arc::new(Semaphore::new(limit.max(1))))
+        .clone()
+}
+
+/// Send the request built by `build` and read the body, retrying transient
+/// failures. A server that keeps rate limiting us is left alone until the
+/// delay it asked for, so that the next polls do not hammer it.
+async fn send(url: &str, build: impl Fn() -> RequestBuilder) -> Result<String, Error> {
+    let origin = origin(url);
+    let semaphore = server_semaphore(url);
+    let retry = &http().retry;
+    let max_backoff = Duration::from_millis(retry.max_backoff_ms);
+    let mut attempt = 1;
+    loop {
+        if let Some(remaining) = origin.as_deref().and_then(rate_limit_remaining) {
+            return Err(Error::RateLimited {
+                retry_after_secs: Some(remaining.as_secs() + 1),
+            });
+        }
+        // the permit is not held while waiting before the next attempt
+        let permit = semaphore.acquire().await.expect("server budget closed");
+        let result = match handle_status(build().send().await) {
+            Ok(r) => r.text().await.map_err(Error::from),
+            Err(e) => Err(e),
+        };
+        drop(permit);
+        let e = match result {
+            Ok(text) => return Ok(text),
+            Err(e) if !e.is_transient() => return Err(e),
+            Err(e) => e,
+        };
+        let delay = match e {
+            Error::RateLimited {
+                retry_after_secs: Some(secs),
+            } => Duration::from_secs(secs),
+            _ => backoff(retry, attempt, random()),
+        };
+        if attempt >= retry.attempts || delay > max_backoff {
+            if let (Error::RateLimited { retry_after_secs }, Some(origin)) = (&e, &origin) {
+                // the delay asked by the server, a guess when it did not tell
+                let delay = retry_after_secs.map_or(max_backoff, Duration::from_secs);
+                set_rate_limited(origin, delay);
+            }
+            return Err(e);
+        }
+        tokio::time::sleep(delay).await;
+        attempt += 1;
     }
 }
 
+pub fn parse_json<T: serde::de::DeserializeOwned>(text: &str) -> Result<T, Error> {
+    // keep track of the path, to report the offending field
+    let de = &mut serde_json::Deserializer::from_str(text);
+    serde_path_to_error::deserialize::<_, T>(de).map_err(Error::from)
+}
+
+pub fn parse_xml<T: serde::de::DeserializeOwned>(text: &str) -> Result<T, Error> {
+    let de = &mut quick_xml::de::Deserializer::from_str(text);
+    serde_path_to_error::deserialize::<_, T>(de).map_err(Error::from)
+}
+
 pub async fn request<T: serde::de::DeserializeOwned>(
     url: &str,
     headers: &Vec<(String, String)>,
-) -> Result<T, String> {
-    resp_to_json::<T>(handle_status(send_request(url, headers).await)).await
+) -> Result<T, Error> {
+    parse_json::<T>(&send(url, || build_request(url, headers)).await?)
 }
 
 pub async fn request_basic<T: serde::de::DeserializeOwned>(
@@ -66,9 +410,176 @@ pub async fn request_basic<T: serde::de::DeserializeOwned>(
     headers: &Vec<(String, String)>,
     user: String,
     password: Option<String>,
-) -> Result<T, String> {
-    resp_to_json::<T>(handle_status(
-        send_request_basic(url, headers, user, password).await,
-    ))
-    .await
+) -> Result<T, Error> {
+    let build = || build_request(url, headers).basic_auth(&user, password.as_ref());
+    parse_json::<T>(&send(url, build).await?)
+}
+
+/// Same as `request` for XML documents, with basic auth when `user` is set
+pub async fn request_xml<T: serde::de::DeserializeOwned>(
+    url: &str,
+    headers: &Vec<(String, String)>,
+    user: &Option<String>,
+    password: &Option<String>,
+) -> Result<T, Error> {
+    let build = || match user {
+        Some(user) => build_request(url, headers).basic_auth(user, password.as_ref()),
+        None => build_request(url, headers),
+    };
+    parse_xml::<T>(&send(url, build).await?)
+}
+
+#[cfg(test)]
+mod utils_tests {
+    use super::*;
+
+    fn headers(values: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in values {
+            headers.insert(*name, value.parse().unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn rate_limit_headers() {
+        let now = 1_700_000_000;
+        assert_eq!(
+            retry_after_secs(&headers(&[("retry-after", "30")]), now),
+            Some(30)
+        );
+        assert_eq!(retry_after_secs(&headers(&[]), now), None);
+        // github
+        let github = headers(&[
+            ("x-ratelimit-remaining", "0"),
+            ("x-ratelimit-reset", "1700000120"),
+        ]);
+        assert!(is_rate_limit_exhausted(&github));
+        assert_eq!(retry_after_secs(&github, now), Some(120));
+        // gitlab
+        let gitlab = headers(&[
+            ("ratelimit-remaining", "0"),
+            ("ratelimit-reset", "1700000010"),
+        ]);
+        assert_eq!(retry_after_secs(&gitlab, now), Some(10));
+        // not exhausted, the reset time does not matter
+        let remaining = headers(&[
+            ("x-ratelimit-remaining", "12"),
+            ("x-ratelimit-reset", "1700000120"),
+        ]);
+        assert!(!is_rate_limit_exhausted(&remaining));
+        assert_eq!(retry_after_secs(&remaining, now), None);
+    }
+
+    #[test]
+    fn rate_limit_statuses() {
+        let retry_after = headers(&[("retry-after", "2")]);
+        assert_eq!(
+            status_error(503, &retry_after),
+            Error::RateLimited {
+                retry_after_secs: Some(2)
+            }
+        );
+        assert_eq!(status_error(503, &headers(&[])), Error::Http(503));
+        assert_eq!(
+            status_error(429, &headers(&[])),
+            Error::RateLimited {
+                retry_after_secs: None
+            }
+        );
+        assert_eq!(status_error(403, &retry_after), Error::Auth(403));
+    }
+
+    #[test]
+    fn exponential_backoff() {
+        let retry = RetryConfig {
+            attempts: 5,
+            initial_backoff_ms: 1000,
+            max_backoff_ms: 5000,
+        };
+        assert_eq!(backoff(&retry, 1, 0), Duration::from_millis(500));
+        assert_eq!(backoff(&retry, 1, 500), Duration::from_millis(1000));
+        assert_eq!(backoff(&retry, 2, 0), Duration::from_millis(1000));
+        assert_eq!(backoff(&retry, 3, 0), Duration::from_millis(2000));
+        assert_eq!(backoff(&retry, 4, 0), Duration::from_millis(2500));
+        assert_eq!(backoff(&retry, 4, 2500), Duration::from_millis(5000));
+        assert_eq!(backoff(&retry, 60, 0), Duration::from_millis(2500));
+        for _ in 0..100 {
+            let delay = backoff(&retry, 2, random());
+            assert!(delay >= Duration::from_millis(1000) && delay <= Duration::from_millis(2000));
+        }
+    }
+
+    #[test]
+    fn rate_limited_origin() {
+        assert_eq!(
+            origin("https://api.github.com/repos/a/b/actions/runs").as_deref(),
+            Some("https://api.github.com")
+        );
+        let origin = "https://rate.limited:8443";
+        assert_eq!(rate_limit_remaining(origin), None);
+        set_rate_limited(origin, Duration::from_secs(60));
+        assert!(rate_limit_remaining(origin).unwrap() > Duration::from_secs(50));
+        set_rate_limited(origin, Duration::ZERO);
+        assert_eq!(rate_limit_remaining(origin), None);
+    }
+
+    #[test]
+    fn encode_components() {
+        assert_eq!(
+            encode_uri_component("my-group/my.project"),
+            "my-group%2Fmy.project"
+        );
+        assert_eq!(
+            encode_uri_component("feature/50% off #2?a=b&c+d"),
+            "feature%2F50%25%20off%20%232%3Fa%3Db%26c%2Bd"
+        );
+        assert_eq!(encode_uri_component("été"), "%C3%A9t%C3%A9");
+    }
+
+    #[test]
+    fn insecure_servers() {
+        let server = "https://my.jenkins";
+        assert!(is_on_server("https://my.jenkins/job/a/api/json", server));
+        assert!(is_on_server("https://MY.jenkins:443/job/a", server));
+        // look-alikes keep verifying the certificates
+        assert!(!is_on_server("https://my.jenkins.evil.com/job/a", server));
+        assert!(!is_on_server("https://my.jenkinsfoo.io/job/a", server));
+        assert!(!is_on_server("https://my.jenkins:8443/job/a", server));
+        assert!(!is_on_server("http://my.jenkins/job/a", server));
+        assert!(!is_on_server("https://my.jenkins@evil.com/job/a", server));
+        let server = "https://my.ci/jenkins/";
+        assert!(is_on_server("https://my.ci/jenkins/job/a", server));
+        assert!(is_on_server("https://my.ci/jenkins", server));
+        assert!(!is_on_server("https://my.ci/jenkins-other/job/a", server));
+    }
+
+    #[test]
+    fn server_budgets() {
+        let limits = HashMap::from([
+            (String::from("https://my.jenkins"), 2),
+            (String::from("https://other.jenkins:8443/"), 1),
+        ]);
+        assert_eq!(
+            server_budget("https://my.jenkins/job/fast/api/json", 4, &limits),
+            (String::from("https://my.jenkins"), 2)
+        );
+        assert_eq!(
+            server_budget("https://other.jenkins:8443/job/slow/api/json", 4, &limits),
+            (String::from("https://other.jenkins:8443"), 1)
+        );
+        // look-alikes have their own budget
+        assert_eq!(
+            server_budget("https://my.jenkins.other/job/fast/api/json", 4, &limits),
+            (String::from("https://my.jenkins.other"), 4)
+        );
+        assert_eq!(
+            server_budget(
+                "https://circleci.com/api/v2/pipeline/abc/workflow",
+                4,
+                &limits
+            ),
+            (String::from("https://circleci.com"), 4)
+        );
+    }
 }
@@ -0,0 +1,111 @@
+use std::time::Duration;
+
+use rwatch::{
+    build_status::BuildStatus,
+    cctray,
+    config::Config,
+    error::Error,
+    rendering::{OutputFormat, RenderOptions, render_json_lines, render_results},
+};
+
+use crate::transitions::Transitions;
+
+pub const DEFAULT_POLLING_INTERVAL: u64 = 60000;
+const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+
+/// Servers that rate limit us are not polled until they allow it, keep
+/// showing the last known status of their builds meanwhile
+pub fn keep_rate_limited(
+    previous: Vec<Result<BuildStatus, Error>>,
+    results: Vec<Result<BuildStatus, Error>>,
+) -> Vec<Result<BuildStatus, Error>> {
+    if previous.len() != results.len() {
+        return results;
+    }
+    previous
+        .into_iter()
+        .zip(results)
+        .map(|(previous, result)| keep_if_rate_limited(Some(previous), result))
+        .collect()
+}
+
+/// The previous status of a single build when its server rate limits us,
+/// `None` when it was not fetched yet
+pub fn keep_if_rate_limited(
+    previous: Option<Result<BuildStatus, Error>>,
+    result: Result<BuildStatus, Error>,
+) -> Result<BuildStatus, Error> {
+    match (previous, result) {
+        (Some(Ok(previous)), Err(Error::RateLimited { .. })) => Ok(previous),
+        (_, result) => result,
+    }
+}
+
+/// Nothing is printed when `quiet`, only notifications and hooks run
+pub async fn watch(
+    config: &Config,
+    options: &RenderOptions,
+    mut transitions: Transitions,
+    quiet: bool,
+) {
+    let polling_interval = config.polling_interval.unwrap_or(DEFAULT_POLLING_INTERVAL);
+    let delay = Duration::from_millis(polling_interval);
+    let mut results = Vec::new();
+    loop {
+        // fetch before clearing, so that the previous table stays
+        // on screen while requests are in flight
+        results = keep_rate_limited(results, config.fetch_all().await);
+        transitions.update(config, &results);
+        if !quiet {
+            match options.format {
+                OutputFormat::Json => render_json_lines(&config.builds, &results),
+                OutputFormat::CCTray => println!("{}", cctray::to_feed(&config.builds, &results)),
+                OutputFormat::Table => {
+                    print!("{CLEAR_SCREEN}");
+                    render_results(&config.builds, &results, options);
+                    let now = chrono::Local::now().format("%H:%M:%S");
+                    println!();
+                    println!(
+                        "Last refresh {now}, next in {}",
+                        pretty_duration::pretty_duration(&delay, None)
+                    );
+                }
+            }
+        }
+        tokio::time::sleep(delay).await;
+    }
+}
+
+#[cfg(test)]
+mod watch_tests {
+    use super::*;
+    use rwatch::build_status::Status;
+
+    fn green() -> Result<BuildStatus, Error> {
+        Ok(BuildStatus {
+            status: Status::Green,
+            url: String::from("http://my.ci/1"),
+            time_info: None,
+            running: None,
+        })
+    }
+
+    #[test]
+    fn rate_limited_builds() {
+        let rate_limited = || {
+            Err(Error::RateLimited {
+                retry_after_secs: Some(60),
+            })
+        };
+        // nothing to keep on the first poll
+        assert_eq!(
+            keep_rate_limited(vec![], vec![rate_limited()]),
+            vec![rate_limited()]
+        );
+        let results = keep_rate_limited(
+            vec![green(), green(), Err(Error::Timeout)],
+            vec![rate_limited(), Err(Error::NotFound), rate_limited()],
+        );
+        assert_eq!(results, vec![green(), Err(Error::NotFound), rate_limited()]);
+    }
+}